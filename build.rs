@@ -0,0 +1,4 @@
+// Build lại khi thêm file migration mới để `sqlx::migrate!` nhúng đủ
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
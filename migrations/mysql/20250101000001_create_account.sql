@@ -0,0 +1,17 @@
+-- Bảng tài khoản dùng chung với game server, giữ nguyên nếu đã tồn tại
+CREATE TABLE IF NOT EXISTS account (
+    id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    username VARCHAR(50) NOT NULL,
+    password VARCHAR(255) NOT NULL,
+    is_admin TINYINT(1) NOT NULL DEFAULT 0,
+    active TINYINT(1) NOT NULL DEFAULT 0,
+    thoi_vang INT NOT NULL DEFAULT 0,
+    vnd INT NOT NULL DEFAULT 0,
+    tongnap INT NOT NULL DEFAULT 0,
+    server_login INT NOT NULL DEFAULT 1,
+    last_time_login DATETIME NOT NULL DEFAULT '2000-01-01 00:00:00',
+    last_time_logout DATETIME NOT NULL DEFAULT '2000-01-01 00:00:00',
+    reward TEXT NULL,
+    ban TINYINT(1) NOT NULL DEFAULT 0,
+    UNIQUE KEY uk_account_username (username)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
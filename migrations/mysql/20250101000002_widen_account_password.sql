@@ -0,0 +1,2 @@
+-- Hash Argon2 (PHC) dài hơn cột password của schema cũ
+ALTER TABLE account MODIFY password VARCHAR(255) NOT NULL;
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS login_history (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    account_id INT NULL,
+    username VARCHAR(64) NOT NULL,
+    server_id INT NOT NULL,
+    client_id INT NOT NULL,
+    peer_addr VARCHAR(64) NOT NULL,
+    result VARCHAR(32) NOT NULL,
+    created_at DATETIME(3) NOT NULL,
+    INDEX idx_login_history_account (account_id, created_at),
+    INDEX idx_login_history_username (username, created_at)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
@@ -0,0 +1,16 @@
+-- Bảng tài khoản dùng chung với game server, giữ nguyên nếu đã tồn tại
+CREATE TABLE IF NOT EXISTS account (
+    id SERIAL PRIMARY KEY,
+    username VARCHAR(50) NOT NULL UNIQUE,
+    password VARCHAR(255) NOT NULL,
+    is_admin BOOLEAN NOT NULL DEFAULT FALSE,
+    active BOOLEAN NOT NULL DEFAULT FALSE,
+    thoi_vang INTEGER NOT NULL DEFAULT 0,
+    vnd INTEGER NOT NULL DEFAULT 0,
+    tongnap INTEGER NOT NULL DEFAULT 0,
+    server_login INTEGER NOT NULL DEFAULT 1,
+    last_time_login TIMESTAMPTZ NOT NULL DEFAULT '2000-01-01 00:00:00+00',
+    last_time_logout TIMESTAMPTZ NOT NULL DEFAULT '2000-01-01 00:00:00+00',
+    reward TEXT NULL,
+    ban BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,2 @@
+-- Hash Argon2 (PHC) dài hơn cột password của schema cũ
+ALTER TABLE account ALTER COLUMN password TYPE VARCHAR(255);
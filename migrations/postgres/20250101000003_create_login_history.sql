@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS login_history (
+    id BIGSERIAL PRIMARY KEY,
+    account_id INTEGER NULL,
+    username VARCHAR(64) NOT NULL,
+    server_id INTEGER NOT NULL,
+    client_id INTEGER NOT NULL,
+    peer_addr VARCHAR(64) NOT NULL,
+    result VARCHAR(32) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_login_history_account ON login_history (account_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_login_history_username ON login_history (username, created_at);
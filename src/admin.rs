@@ -0,0 +1,369 @@
+use anyhow::Result;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::context::AppContext;
+use crate::io::service::{BROADCAST_CLIENT_ID, Service};
+use crate::model::maintenance::MaintenanceWindow;
+use crate::model::user_manager::UserInfo;
+
+#[derive(Debug, Error)]
+pub enum AdminError {
+    #[error("missing or invalid admin token")]
+    Unauthorized,
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    BadRequest(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            AdminError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AdminError::NotFound(_) => StatusCode::NOT_FOUND,
+            AdminError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AdminError::Internal(e) => {
+                warn!("Admin API error: {:#}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+#[derive(Clone)]
+struct AdminState {
+    app: AppContext,
+    token: Arc<str>,
+    started_at: Instant,
+}
+
+/// Các route của API quản trị, mọi request phải có `Authorization: Bearer <token>`
+pub fn router(app: AppContext, token: &str) -> Router {
+    let state = AdminState {
+        app,
+        token: Arc::from(token),
+        started_at: Instant::now(),
+    };
+    Router::new()
+        .route("/status", get(status))
+        .route("/users", get(list_users))
+        .route("/users/:user_id/kick", post(kick_user))
+        .route("/accounts/:user_id/ban", post(ban_account))
+        .route("/accounts/:user_id/unban", post(unban_account))
+        .route("/broadcast", post(broadcast))
+        .route("/maintenance", get(list_maintenance))
+        .route(
+            "/maintenance/:server_id",
+            put(start_maintenance).delete(end_maintenance),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// Phục vụ API quản trị cho tới khi `shutdown` bị hủy
+pub async fn serve(
+    listener: TcpListener,
+    app: AppContext,
+    token: &str,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    axum::serve(listener, router(app, token))
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await?;
+    Ok(())
+}
+
+async fn require_token(
+    State(state): State<AdminState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AdminError> {
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // Token rỗng (cấu hình sai) không bao giờ hợp lệ
+    if state.token.is_empty() || !bool::from(provided.as_bytes().ct_eq(state.token.as_bytes())) {
+        return Err(AdminError::Unauthorized);
+    }
+    Ok(next.run(request).await)
+}
+
+#[derive(Debug, Serialize)]
+struct ServerStatus {
+    server_id: i32,
+    connected: bool,
+    online_users: usize,
+    rtt_ms: Option<f64>,
+    maintenance: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Status {
+    uptime_secs: u64,
+    online_users: usize,
+    servers: Vec<ServerStatus>,
+}
+
+async fn status(State(state): State<AdminState>) -> Json<Status> {
+    let app = &state.app;
+    let users = app.user_manager.all().await;
+    let maintenance: Vec<i32> = app
+        .maintenance
+        .all()
+        .await
+        .into_iter()
+        .map(|(server_id, _)| server_id)
+        .collect();
+    let mut servers: BTreeMap<i32, ServerStatus> = BTreeMap::new();
+    for (server_id, handle) in app.server_registry.all().await {
+        servers.insert(
+            server_id,
+            ServerStatus {
+                server_id,
+                connected: true,
+                online_users: 0,
+                rtt_ms: handle.rtt().map(|rtt| rtt.as_secs_f64() * 1000.0),
+                maintenance: maintenance.contains(&server_id),
+            },
+        );
+    }
+    // Server mất kết nối nhưng user chưa bị dọn vẫn được liệt kê
+    for user in &users {
+        servers
+            .entry(user.server_id)
+            .or_insert_with(|| ServerStatus {
+                server_id: user.server_id,
+                connected: false,
+                online_users: 0,
+                rtt_ms: None,
+                maintenance: maintenance.contains(&user.server_id),
+            })
+            .online_users += 1;
+    }
+    Json(Status {
+        uptime_secs: state.started_at.elapsed().as_secs(),
+        online_users: users.len(),
+        servers: servers.into_values().collect(),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct UserQuery {
+    server_id: Option<i32>,
+    /// Một phần username, không phân biệt hoa thường
+    q: Option<String>,
+}
+
+async fn list_users(
+    State(state): State<AdminState>,
+    Query(query): Query<UserQuery>,
+) -> Json<Vec<UserInfo>> {
+    let needle = query.q.map(|q| q.to_lowercase());
+    let mut users: Vec<UserInfo> = state
+        .app
+        .user_manager
+        .all()
+        .await
+        .into_iter()
+        .filter(|user| query.server_id.is_none_or(|id| user.server_id == id))
+        .filter(|user| {
+            needle
+                .as_deref()
+                .is_none_or(|needle| user.username.to_lowercase().contains(needle))
+        })
+        .collect();
+    users.sort_by_key(|user| user.user_id);
+    Json(users)
+}
+
+#[derive(Debug, Serialize)]
+struct Kicked {
+    user_id: i32,
+    server_id: i32,
+    /// `false` nếu game server chưa kết nối, DISCONNECT sẽ được gửi khi server đó quay lại
+    delivered: bool,
+}
+
+/// Gửi DISCONNECT tới game server đang giữ user rồi xóa user khỏi danh sách online
+async fn kick_online(app: &AppContext, user_id: i32) -> Option<Kicked> {
+    let online = app.user_manager.find(user_id).await?;
+    let delivered = app.server_registry.kick(online.server_id, user_id).await;
+    app.user_manager.remove(user_id).await;
+    if let Err(e) = app.accounts.update_logout_time(user_id).await {
+        warn!("Cannot update logout time of {}: {}", user_id, e);
+    }
+    Some(Kicked {
+        user_id,
+        server_id: online.server_id,
+        delivered,
+    })
+}
+
+async fn kick_user(
+    State(state): State<AdminState>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<Kicked>, AdminError> {
+    let kicked = kick_online(&state.app, user_id)
+        .await
+        .ok_or_else(|| AdminError::NotFound(format!("user {} is not online", user_id)))?;
+    info!(
+        "Admin kicked user {} from server {}",
+        user_id, kicked.server_id
+    );
+    Ok(Json(kicked))
+}
+
+#[derive(Debug, Serialize)]
+struct BanResult {
+    user_id: i32,
+    ban: bool,
+    /// Tài khoản bị khóa khi đang online thì bị kick luôn
+    kicked: Option<Kicked>,
+}
+
+async fn set_ban(state: &AdminState, user_id: i32, ban: bool) -> Result<BanResult, AdminError> {
+    if !state.app.accounts.set_ban(user_id, ban).await? {
+        return Err(AdminError::NotFound(format!(
+            "account {} does not exist",
+            user_id
+        )));
+    }
+    let kicked = if ban {
+        kick_online(&state.app, user_id).await
+    } else {
+        None
+    };
+    info!(
+        "Admin {} account {}",
+        if ban { "banned" } else { "unbanned" },
+        user_id
+    );
+    Ok(BanResult {
+        user_id,
+        ban,
+        kicked,
+    })
+}
+
+async fn ban_account(
+    State(state): State<AdminState>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<BanResult>, AdminError> {
+    set_ban(&state, user_id, true).await.map(Json)
+}
+
+async fn unban_account(
+    State(state): State<AdminState>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<BanResult>, AdminError> {
+    set_ban(&state, user_id, false).await.map(Json)
+}
+
+#[derive(Debug, Deserialize)]
+struct BroadcastRequest {
+    text: String,
+    /// Bỏ trống để gửi tới mọi game server
+    server_id: Option<i32>,
+}
+
+async fn broadcast(
+    State(state): State<AdminState>,
+    Json(request): Json<BroadcastRequest>,
+) -> Result<Json<serde_json::Value>, AdminError> {
+    if request.text.trim().is_empty() {
+        return Err(AdminError::BadRequest("text must not be empty".to_string()));
+    }
+    let servers: Vec<_> = state
+        .app
+        .server_registry
+        .all()
+        .await
+        .into_iter()
+        .filter(|(server_id, _)| request.server_id.is_none_or(|id| *server_id == id))
+        .collect();
+    if let Some(server_id) = request.server_id
+        && servers.is_empty()
+    {
+        return Err(AdminError::NotFound(format!(
+            "server {} is not connected",
+            server_id
+        )));
+    }
+    let mut notified = 0;
+    for (server_id, handle) in servers {
+        match Service::server_message(&handle, BROADCAST_CLIENT_ID, &request.text).await {
+            Ok(()) => notified += 1,
+            Err(e) => warn!("Cannot broadcast to server {}: {}", server_id, e),
+        }
+    }
+    info!("Admin broadcast to {} game servers", notified);
+    Ok(Json(json!({ "notified": notified })))
+}
+
+#[derive(Debug, Serialize)]
+struct ServerMaintenance {
+    server_id: i32,
+    #[serde(flatten)]
+    window: MaintenanceWindow,
+}
+
+async fn list_maintenance(State(state): State<AdminState>) -> Json<Vec<ServerMaintenance>> {
+    let servers = state.app.maintenance.all().await;
+    Json(
+        servers
+            .into_iter()
+            .map(|(server_id, window)| ServerMaintenance { server_id, window })
+            .collect(),
+    )
+}
+
+async fn start_maintenance(
+    State(state): State<AdminState>,
+    Path(server_id): Path<i32>,
+    Json(window): Json<MaintenanceWindow>,
+) -> Result<Json<ServerMaintenance>, AdminError> {
+    if window.until.is_some_and(|until| until <= Utc::now()) {
+        return Err(AdminError::BadRequest(
+            "until must be in the future".to_string(),
+        ));
+    }
+    state.app.maintenance.set(server_id, window.clone()).await;
+    info!("Admin started maintenance on server {}", server_id);
+    Ok(Json(ServerMaintenance { server_id, window }))
+}
+
+async fn end_maintenance(
+    State(state): State<AdminState>,
+    Path(server_id): Path<i32>,
+) -> Result<Json<serde_json::Value>, AdminError> {
+    if !state.app.maintenance.clear(server_id).await {
+        return Err(AdminError::NotFound(format!(
+            "server {} is not in maintenance",
+            server_id
+        )));
+    }
+    info!("Admin ended maintenance on server {}", server_id);
+    Ok(Json(json!({ "server_id": server_id })))
+}
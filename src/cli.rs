@@ -0,0 +1,76 @@
+use anyhow::{Result, bail};
+
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+pub const USAGE: &str = "usage: login_server_rust [--config <path>] [migrate up|status]";
+
+/// Tham số dòng lệnh
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub config_path: String,
+    /// Lệnh con và tham số của nó, rỗng là chạy server
+    pub command: Vec<String>,
+}
+
+impl Cli {
+    /// `args` không gồm tên chương trình
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut config_path = None;
+        let mut command = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let path = match arg.as_str() {
+                "-c" | "--config" => match args.next() {
+                    Some(path) => path,
+                    None => bail!("{} requires a path ({})", arg, USAGE),
+                },
+                _ => match arg.strip_prefix("--config=") {
+                    Some(path) => path.to_string(),
+                    None if arg.starts_with('-') => bail!("Unknown option: {} ({})", arg, USAGE),
+                    None => {
+                        command.push(arg);
+                        continue;
+                    }
+                },
+            };
+            if config_path.replace(path).is_some() {
+                bail!("--config given more than once");
+            }
+        }
+        Ok(Self {
+            config_path: config_path.unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string()),
+            command,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli> {
+        Cli::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn config_flag_in_any_position() {
+        assert_eq!(
+            parse(&[]).unwrap(),
+            Cli {
+                config_path: "config.toml".to_string(),
+                command: vec![],
+            }
+        );
+        let cli = parse(&["migrate", "--config", "/etc/login/config.toml", "status"]).unwrap();
+        assert_eq!(cli.config_path, "/etc/login/config.toml");
+        assert_eq!(cli.command, vec!["migrate", "status"]);
+        assert_eq!(parse(&["--config=a.toml"]).unwrap().config_path, "a.toml");
+    }
+
+    #[test]
+    fn bad_flags_are_rejected() {
+        assert!(parse(&["--config"]).is_err());
+        assert!(parse(&["-c", "a.toml", "-c", "b.toml"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+    }
+}
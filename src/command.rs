@@ -1,8 +1,12 @@
-pub mod command {
-    pub const LOGIN: i8 = 1;
-    pub const LOGOUT: i8 = 2;
-    pub const DISCONNECT: i8 = 3;
-    pub const SERVER_MESSAGE: i8 = 4;
-    pub const SET_SERVER: i8 = 5;
-    pub const UPDATE_TIME_LOGOUT: i8 = 6;
-}
+pub const SEND_KEY: i8 = -27;
+pub const LOGIN: i8 = 1;
+pub const LOGOUT: i8 = 2;
+pub const DISCONNECT: i8 = 3;
+pub const SERVER_MESSAGE: i8 = 4;
+pub const SET_SERVER: i8 = 5;
+pub const UPDATE_TIME_LOGOUT: i8 = 6;
+pub const AUTH_CHALLENGE: i8 = 7;
+pub const AUTH_RESPONSE: i8 = 8;
+pub const AUTH_RESULT: i8 = 9;
+pub const PING: i8 = 10;
+pub const PONG: i8 = 11;
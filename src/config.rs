@@ -1,35 +1,775 @@
-use anyhow::{Ok, Result};
+use anyhow::{Context, Result};
+use parking_lot::RwLock;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
 
-#[derive(Debug, Deserialize, Clone)]
+/// Tiền tố biến môi trường ghi đè config, `__` phân cách các cấp:
+/// `LOGIN_SERVER__DATABASE__PASSWORD` ghi đè `[database] password`
+pub const ENV_PREFIX: &str = "LOGIN_SERVER__";
+
+/// Lỗi config, luôn nêu tên mục bị sai
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConfigError {
+    #[error("invalid config `{field}`: {reason}")]
+    Invalid { field: String, reason: String },
+    #[error("invalid environment override {var}: {reason}")]
+    Env { var: String, reason: String },
+}
+
+fn invalid(field: &str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid {
+        field: field.to_string(),
+        reason: reason.into(),
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct Config {
     pub server: ServerConfig,
     pub database: DatabaseConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub password: PasswordConfig,
+    #[serde(default)]
+    pub login_limit: LoginLimitConfig,
+    #[serde(default)]
+    pub login_history: LoginHistoryConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    #[serde(default)]
+    pub online_state: OnlineStateConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct ServerConfig {
     pub listen_port: u16,
     pub second_wait_login: i32,
     pub testmode: i32,
+    #[serde(default)]
+    pub string_encoding: StringEncoding,
+    /// Dùng key XOR cố định "vmn" cho các bản game server cũ thay vì key ngẫu nhiên
+    #[serde(default)]
+    pub legacy_xor_key: bool,
+    /// Thời gian chờ game server mất kết nối quay lại trước khi xóa user của nó khỏi danh sách online
+    #[serde(default = "default_link_grace_secs")]
+    pub link_grace_secs: u64,
+    /// Listener TLS riêng cho game server ở datacenter khác, bỏ trống để tắt
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+fn default_link_grace_secs() -> u64 {
+    60
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TlsConfig {
+    pub listen_port: u16,
+    pub cert_path: String,
+    pub key_path: String,
+    /// CA để xác thực certificate của game server (mutual TLS)
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+}
+
+/// Cách mã hóa chuỗi trong `read_utf` / `write_utf`
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StringEncoding {
+    /// Modified UTF-8 của Java (`DataOutputStream.writeUTF`)
+    #[default]
+    ModifiedUtf8,
+    /// UTF-8 chuẩn
+    Utf8,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct DatabaseConfig {
+    #[serde(default)]
+    pub driver: DatabaseDriver,
     pub host: String,
     pub port: u16,
     pub database_name: String,
     pub username: String,
+    #[serde(default)]
     pub password: String,
+    /// Đọc mật khẩu từ file (Docker / k8s secret) thay cho `password`
+    #[serde(default)]
+    pub password_file: Option<String>,
     pub min_connections: u32,
     pub max_connections: u32,
+    /// Tự chạy migration còn thiếu khi khởi động
+    #[serde(default)]
+    pub auto_migrate: bool,
+}
+
+/// Loại database, `postgres` cần build với feature `postgres`
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseDriver {
+    #[default]
+    Mysql,
+    Postgres,
+}
+
+/// Xác thực game server bằng HMAC, secret theo từng server_id
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// server_id -> shared secret
+    #[serde(default)]
+    pub secrets: HashMap<String, String>,
+}
+
+impl AuthConfig {
+    pub fn secret_for(&self, server_id: i32) -> Option<&str> {
+        self.secrets.get(&server_id.to_string()).map(String::as_str)
+    }
+}
+
+/// Tham số Argon2id cho hash mật khẩu
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct PasswordConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19456,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Chống dò mật khẩu: khóa tạm username / game server sau nhiều lần đăng nhập sai
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LoginLimitConfig {
+    pub enabled: bool,
+    /// Số lần sai liên tiếp của một username trước khi bị khóa
+    pub username_max_failures: u32,
+    /// Số lần sai từ một game server (mọi username) trước khi khóa cả server đó
+    pub server_max_failures: u32,
+    /// Thời gian khóa lần đầu, nhân đôi sau mỗi lần sai tiếp theo
+    pub base_lock_secs: u64,
+    pub max_lock_secs: u64,
+    /// Quên các lần sai cũ hơn khoảng này
+    pub failure_window_secs: u64,
+}
+
+impl Default for LoginLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            username_max_failures: 5,
+            server_max_failures: 200,
+            base_lock_secs: 30,
+            max_lock_secs: 3600,
+            failure_window_secs: 900,
+        }
+    }
+}
+
+/// Ghi lịch sử mọi lần LOGIN vào bảng `login_history`
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LoginHistoryConfig {
+    pub enabled: bool,
+    /// Số bản ghi tối đa trong một câu INSERT
+    pub batch_size: usize,
+    /// Ghi batch chưa đầy sau khoảng này
+    pub flush_interval_ms: u64,
+    /// Số bản ghi chờ tối đa, vượt quá thì bỏ bớt để không làm chậm LOGIN
+    pub queue_size: usize,
+}
+
+impl Default for LoginHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            batch_size: 100,
+            flush_interval_ms: 1000,
+            queue_size: 10_000,
+        }
+    }
+}
+
+/// Tắt server khi nhận SIGTERM / Ctrl-C
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// Thời gian tối đa chờ các session xử lý xong gói tin đang dở
+    pub drain_timeout_secs: u64,
+    /// SERVER_MESSAGE gửi tới các game server trước khi tắt
+    pub message: String,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_timeout_secs: 10,
+            message: "Máy chủ đăng nhập đang khởi động lại, vui lòng chờ trong giây lát."
+                .to_string(),
+        }
+    }
+}
+
+/// Lưu danh sách user online để không mất khi restart login server
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct OnlineStateConfig {
+    pub enabled: bool,
+    pub path: String,
+    pub save_interval_secs: u64,
+}
+
+impl Default for OnlineStateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: "online_users.json".to_string(),
+            save_interval_secs: 5,
+        }
+    }
+}
+
+/// Endpoint `/metrics` cho Prometheus, chạy trên port riêng
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub listen_port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_port: 9100,
+        }
+    }
+}
+
+/// HTTP API quản trị, chỉ lắng nghe trên 127.0.0.1
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AdminConfig {
+    pub enabled: bool,
+    pub listen_port: u16,
+    /// Gửi kèm mọi request dạng `Authorization: Bearer <token>`
+    pub token: String,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_port: 9200,
+            token: String::new(),
+        }
+    }
+}
+
+/// Heartbeat và giới hạn thời gian đọc của kết nối game server, 0 là tắt
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Chu kỳ gửi PING tới game server
+    pub ping_interval_secs: u64,
+    /// Đóng kết nối không gửi gói tin nào (kể cả PONG) trong khoảng này.
+    /// Mặc định 0 (tắt) vì game server bản cũ không trả lời PING
+    pub idle_timeout_secs: u64,
+    /// Đóng kết nối gửi dở một frame quá lâu (slowloris)
+    pub frame_timeout_secs: u64,
+    /// Đóng kết nối sau từng này PING liên tiếp không có PONG, 0 là tắt.
+    /// Game server chưa từng trả lời PING không bị tính
+    pub max_missed_pings: u32,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval_secs: 30,
+            idle_timeout_secs: 0,
+            frame_timeout_secs: 10,
+            max_missed_pings: 3,
+        }
+    }
+}
+
+impl SessionConfig {
+    pub fn ping_interval(&self) -> Option<Duration> {
+        non_zero_secs(self.ping_interval_secs)
+    }
+
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        non_zero_secs(self.idle_timeout_secs)
+    }
+
+    pub fn frame_timeout(&self) -> Option<Duration> {
+        non_zero_secs(self.frame_timeout_secs)
+    }
+}
+
+fn non_zero_secs(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
 }
 
 impl Config {
+    /// Đọc `path`, áp dụng biến môi trường `LOGIN_SERVER__*`, đọc các file secret rồi kiểm tra
     pub fn load(path: &str) -> Result<Self> {
-        let content = fs::read_to_string(path)?;
-        let config = toml::from_str(&content)?;
+        let content = fs::read_to_string(path).with_context(|| format!("cannot read {}", path))?;
+        let config = Self::parse(&content, std::env::vars())
+            .with_context(|| format!("invalid config file {}", path))?;
+        Ok(config)
+    }
+
+    /// Như `load` nhưng với nội dung và biến môi trường cho trước
+    pub fn parse(content: &str, env: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(content)?;
+        let mut untyped = apply_env_overrides(&mut table, env)?;
+        let mut config: Config = loop {
+            match serde_path_to_error::deserialize(toml::Value::Table(table.clone())) {
+                Ok(config) => break config,
+                Err(e) => {
+                    let field = e.path().to_string();
+                    // Field cần số / bool nhưng giá trị lấy từ biến môi trường đang là chuỗi
+                    match untyped.remove(&field) {
+                        Some(path) if retype_env_value(&mut table, &path) => continue,
+                        _ => return Err(invalid(&field, e.inner().to_string()).into()),
+                    }
+                }
+            }
+        };
+        config.read_secret_files()?;
+        config.validate()?;
         Ok(config)
     }
+
+    fn read_secret_files(&mut self) -> Result<(), ConfigError> {
+        let database = &mut self.database;
+        if let Some(path) = &database.password_file {
+            if !database.password.is_empty() {
+                return Err(invalid(
+                    "database.password_file",
+                    "cannot be used together with database.password",
+                ));
+            }
+            let password = fs::read_to_string(path)
+                .map_err(|e| invalid("database.password_file", format!("{}: {}", path, e)))?;
+            // File secret thường có newline ở cuối
+            database.password = password.trim_end_matches(['\r', '\n']).to_string();
+        }
+        Ok(())
+    }
+
+    /// Kiểm tra các giá trị mà serde không bắt được
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let server = &self.server;
+        if server.listen_port == 0 {
+            return Err(invalid("server.listen_port", "must not be 0"));
+        }
+        if server.second_wait_login < 0 {
+            return Err(invalid("server.second_wait_login", "must not be negative"));
+        }
+        if !matches!(server.testmode, 0 | 1) {
+            return Err(invalid("server.testmode", "must be 0 or 1"));
+        }
+        if let Some(tls) = &server.tls {
+            if tls.listen_port == 0 || tls.listen_port == server.listen_port {
+                return Err(invalid(
+                    "server.tls.listen_port",
+                    "must be non-zero and differ from server.listen_port",
+                ));
+            }
+            for (field, path) in [
+                ("server.tls.cert_path", &tls.cert_path),
+                ("server.tls.key_path", &tls.key_path),
+            ] {
+                if path.is_empty() {
+                    return Err(invalid(field, "must not be empty"));
+                }
+            }
+        }
+
+        let database = &self.database;
+        for (field, value) in [
+            ("database.host", &database.host),
+            ("database.database_name", &database.database_name),
+            ("database.username", &database.username),
+        ] {
+            if value.trim().is_empty() {
+                return Err(invalid(field, "must not be empty"));
+            }
+        }
+        if database.max_connections == 0 {
+            return Err(invalid("database.max_connections", "must be at least 1"));
+        }
+        if database.min_connections > database.max_connections {
+            return Err(invalid(
+                "database.min_connections",
+                format!(
+                    "{} is greater than database.max_connections ({})",
+                    database.min_connections, database.max_connections
+                ),
+            ));
+        }
+
+        for (field, value) in [
+            ("password.memory_kib", self.password.memory_kib),
+            ("password.iterations", self.password.iterations),
+            ("password.parallelism", self.password.parallelism),
+        ] {
+            if value == 0 {
+                return Err(invalid(field, "must be at least 1"));
+            }
+        }
+        if self.login_history.enabled {
+            if self.login_history.batch_size == 0 {
+                return Err(invalid("login_history.batch_size", "must be at least 1"));
+            }
+            if self.login_history.queue_size == 0 {
+                return Err(invalid("login_history.queue_size", "must be at least 1"));
+            }
+            if self.login_history.flush_interval_ms == 0 {
+                return Err(invalid(
+                    "login_history.flush_interval_ms",
+                    "must be at least 1",
+                ));
+            }
+        }
+        if self.online_state.enabled && self.online_state.path.trim().is_empty() {
+            return Err(invalid("online_state.path", "must not be empty"));
+        }
+        if self.auth.enabled && self.auth.secrets.is_empty() {
+            return Err(invalid(
+                "auth.secrets",
+                "at least one secret is required when auth is enabled",
+            ));
+        }
+
+        let mut ports = vec![("server.listen_port", server.listen_port)];
+        if self.metrics.enabled {
+            ports.push(("metrics.listen_port", self.metrics.listen_port));
+        }
+        if self.admin.enabled {
+            if self.admin.token.is_empty() {
+                return Err(invalid(
+                    "admin.token",
+                    "must be set when the admin API is enabled",
+                ));
+            }
+            ports.push(("admin.listen_port", self.admin.listen_port));
+        }
+        for (i, (field, port)) in ports.iter().enumerate() {
+            if *port == 0 {
+                return Err(invalid(field, "must not be 0"));
+            }
+            if let Some((other, _)) = ports[..i].iter().find(|(_, p)| p == port) {
+                return Err(invalid(
+                    field,
+                    format!("port {} is already used by {}", port, other),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Các mục khác nhau giữa `self` và `new` mà chỉ có hiệu lực sau khi khởi động lại
+    pub fn restart_required(&self, new: &Config) -> Vec<&'static str> {
+        let (old, new) = (self, new);
+        [
+            (
+                "server.listen_port",
+                old.server.listen_port != new.server.listen_port,
+            ),
+            ("server.tls", old.server.tls != new.server.tls),
+            (
+                "server.link_grace_secs",
+                old.server.link_grace_secs != new.server.link_grace_secs,
+            ),
+            ("database", old.database != new.database),
+            ("password", old.password != new.password),
+            ("login_limit", old.login_limit != new.login_limit),
+            ("login_history", old.login_history != new.login_history),
+            ("online_state", old.online_state != new.online_state),
+            ("metrics", old.metrics != new.metrics),
+            ("admin", old.admin != new.admin),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    /// Các mục khác nhau giữa `self` và `new` mà chỉ áp dụng cho kết nối game server mới,
+    /// session đang mở vẫn dùng giá trị cũ tới khi kết nối lại
+    pub fn new_connections_only(&self, new: &Config) -> Vec<&'static str> {
+        let (old, new) = (self, new);
+        [
+            (
+                "server.legacy_xor_key",
+                old.server.legacy_xor_key != new.server.legacy_xor_key,
+            ),
+            ("auth", old.auth != new.auth),
+            ("session", old.session != new.session),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+/// Ghi đè từng giá trị trong `table` bằng các biến `LOGIN_SERVER__<SECTION>__<KEY>`.
+/// Giá trị được đọc theo kiểu của giá trị đang có trong file (số, bool, chuỗi);
+/// key chưa có trong file được ghi dạng chuỗi và trả về (theo `section.key`)
+/// để `retype_env_value` đổi kiểu nếu field cần số hoặc bool.
+fn apply_env_overrides(
+    table: &mut toml::Table,
+    env: impl IntoIterator<Item = (String, String)>,
+) -> Result<HashMap<String, Vec<String>>, ConfigError> {
+    let mut untyped = HashMap::new();
+    let mut overrides: Vec<(String, String)> = env
+        .into_iter()
+        .filter(|(var, _)| var.starts_with(ENV_PREFIX))
+        .collect();
+    // Thứ tự cố định để kết quả không phụ thuộc thứ tự biến môi trường
+    overrides.sort();
+    for (var, raw) in overrides {
+        let env_error = |reason: &str| ConfigError::Env {
+            var: var.clone(),
+            reason: reason.to_string(),
+        };
+        let path: Vec<String> = var[ENV_PREFIX.len()..]
+            .split("__")
+            .map(str::to_lowercase)
+            .collect();
+        if path.iter().any(String::is_empty) {
+            return Err(env_error("empty key segment"));
+        }
+        let (key, sections) = path
+            .split_last()
+            .expect("split yields at least one segment");
+        let mut current = &mut *table;
+        for section in sections {
+            current = current
+                .entry(section.clone())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| env_error(&format!("`{}` is not a table", section)))?;
+        }
+        let value = match current.get(key) {
+            Some(toml::Value::String(_)) => toml::Value::String(raw),
+            Some(toml::Value::Integer(_)) => raw
+                .parse()
+                .map(toml::Value::Integer)
+                .map_err(|_| env_error("expected an integer"))?,
+            Some(toml::Value::Float(_)) => raw
+                .parse()
+                .map(toml::Value::Float)
+                .map_err(|_| env_error("expected a number"))?,
+            Some(toml::Value::Boolean(_)) => raw
+                .parse()
+                .map(toml::Value::Boolean)
+                .map_err(|_| env_error("expected true or false"))?,
+            Some(_) => {
+                return Err(env_error(
+                    "only strings, numbers and booleans can be overridden",
+                ));
+            }
+            None => {
+                untyped.insert(path.join("."), path.clone());
+                toml::Value::String(raw)
+            }
+        };
+        current.insert(key.clone(), value);
+    }
+    Ok(untyped)
+}
+
+/// Đổi giá trị chuỗi tại `path` thành số hoặc bool, `false` nếu không đổi được
+fn retype_env_value(table: &mut toml::Table, path: &[String]) -> bool {
+    let Some((key, sections)) = path.split_last() else {
+        return false;
+    };
+    let mut current = table;
+    for section in sections {
+        match current.get_mut(section).and_then(toml::Value::as_table_mut) {
+            Some(table) => current = table,
+            None => return false,
+        }
+    }
+    let Some(toml::Value::String(raw)) = current.get(key) else {
+        return false;
+    };
+    let value = if let Ok(integer) = raw.parse() {
+        toml::Value::Integer(integer)
+    } else if let Ok(float) = raw.parse() {
+        toml::Value::Float(float)
+    } else if let Ok(boolean) = raw.parse() {
+        toml::Value::Boolean(boolean)
+    } else {
+        return false;
+    };
+    current.insert(key.clone(), value);
+    true
+}
+
+/// Config dùng chung, được thay cả khối khi reload. Mỗi lần đọc lấy một bản
+/// `Arc<Config>` nhất quán, session đang xử lý gói tin không thấy config nửa cũ nửa mới.
+#[derive(Clone)]
+pub struct SharedConfig {
+    current: Arc<RwLock<Arc<Config>>>,
+}
+
+impl SharedConfig {
+    pub fn new(config: Config) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(config))),
+        }
+    }
+
+    pub fn load(&self) -> Arc<Config> {
+        self.current.read().clone()
+    }
+
+    pub fn store(&self, config: Config) {
+        *self.current.write() = Arc::new(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::CONFIG;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(var, value)| (var.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn parse_error(content: &str, vars: &[(&str, &str)]) -> String {
+        match Config::parse(content, env(vars)) {
+            Ok(_) => panic!("config should be rejected"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn env_overrides_follow_the_file_types() {
+        let config = Config::parse(
+            CONFIG,
+            env(&[
+                ("LOGIN_SERVER__DATABASE__PASSWORD", "123456"),
+                ("LOGIN_SERVER__DATABASE__MAX_CONNECTIONS", "25"),
+                ("LOGIN_SERVER__SERVER__TESTMODE", "1"),
+                ("UNRELATED", "x"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.database.password, "123456");
+        assert_eq!(config.database.max_connections, 25);
+        assert_eq!(config.server.testmode, 1);
+
+        let error = parse_error(CONFIG, &[("LOGIN_SERVER__DATABASE__PORT", "mysql")]);
+        assert!(error.contains("LOGIN_SERVER__DATABASE__PORT"), "{}", error);
+    }
+
+    #[test]
+    fn env_supplies_keys_missing_from_the_file() {
+        let without_password = CONFIG.replace("password = \"\"\n", "");
+        for password in ["123456", "true", "p@ss"] {
+            let config = Config::parse(
+                &without_password,
+                env(&[("LOGIN_SERVER__DATABASE__PASSWORD", password)]),
+            )
+            .unwrap();
+            assert_eq!(config.database.password, password);
+        }
+
+        let config = Config::parse(
+            CONFIG,
+            env(&[
+                ("LOGIN_SERVER__SESSION__IDLE_TIMEOUT_SECS", "120"),
+                ("LOGIN_SERVER__METRICS__ENABLED", "true"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.session.idle_timeout_secs, 120);
+        assert!(config.metrics.enabled);
+
+        let error = parse_error(
+            CONFIG,
+            &[("LOGIN_SERVER__SESSION__IDLE_TIMEOUT_SECS", "soon")],
+        );
+        assert!(error.contains("session.idle_timeout_secs"), "{}", error);
+    }
+
+    #[test]
+    fn errors_name_the_bad_field() {
+        let error = parse_error(&CONFIG.replace("port = 3306", "port = \"3306\""), &[]);
+        assert!(error.contains("database.port"), "{}", error);
+
+        let error = parse_error(&CONFIG.replace("testmode = 0", "testmode = 2"), &[]);
+        assert!(error.contains("server.testmode"), "{}", error);
+
+        let error = parse_error(
+            &CONFIG.replace("min_connections = 1", "min_connections = 20"),
+            &[],
+        );
+        assert!(error.contains("database.min_connections"), "{}", error);
+
+        let error = parse_error(
+            &format!("{}\n[login_history]\nflush_interval_ms = 0\n", CONFIG),
+            &[],
+        );
+        assert!(
+            error.contains("login_history.flush_interval_ms"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn idle_timeout_is_opt_in() {
+        let config = Config::parse(CONFIG, []).unwrap();
+        assert!(config.session.ping_interval().is_some());
+        assert_eq!(config.session.idle_timeout(), None);
+    }
+
+    #[test]
+    fn password_is_read_from_file() {
+        let dir = std::env::temp_dir().join(format!("login_server_config_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("db_password");
+        fs::write(&path, "s3cr3t\n").unwrap();
+        let with_file = CONFIG.replace(
+            "password = \"\"",
+            &format!("password_file = {:?}", path.to_string_lossy()),
+        );
+
+        let config = Config::parse(&with_file, []).unwrap();
+        assert_eq!(config.database.password, "s3cr3t");
+
+        let error = parse_error(&with_file, &[("LOGIN_SERVER__DATABASE__PASSWORD", "x")]);
+        assert!(error.contains("database.password_file"), "{}", error);
+    }
 }
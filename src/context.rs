@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use crate::config::SharedConfig;
+use crate::db::account::AccountRepository;
+use crate::io::link_cleanup::LinkCleanup;
+use crate::io::server_registry::ServerRegistry;
+use crate::metrics::Metrics;
+use crate::model::login_history::LoginHistory;
+use crate::model::login_limiter::LoginLimiter;
+use crate::model::maintenance::Maintenance;
+use crate::model::password::PasswordHasher;
+use crate::model::user_manager::UserManager;
+
+/// Các thành phần dùng chung giữa mọi session, clone rẻ (đều là handle `Arc`)
+#[derive(Clone)]
+pub struct AppContext {
+    pub accounts: Arc<dyn AccountRepository>,
+    pub user_manager: UserManager,
+    pub server_registry: ServerRegistry,
+    pub link_cleanup: LinkCleanup,
+    pub password_hasher: PasswordHasher,
+    pub login_limiter: LoginLimiter,
+    pub maintenance: Maintenance,
+    pub login_history: LoginHistory,
+    pub metrics: Metrics,
+    pub config: SharedConfig,
+}
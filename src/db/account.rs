@@ -0,0 +1,22 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::model::user::{PasswordMigrationReport, User};
+
+/// Truy cập bảng tài khoản, tách khỏi driver DB cụ thể để `Controller`
+/// có thể chạy với MySQL hoặc bộ nhớ (test)
+#[async_trait]
+pub trait AccountRepository: Send + Sync {
+    async fn find_by_username(&self, username: &str) -> Result<Option<User>>;
+
+    async fn update_password_hash(&self, user_id: i32, hash: &str) -> Result<()>;
+
+    async fn update_login_time(&self, user_id: i32) -> Result<()>;
+
+    async fn update_logout_time(&self, user_id: i32) -> Result<()>;
+
+    /// Khóa / mở khóa tài khoản, trả về `false` nếu không có tài khoản `user_id`
+    async fn set_ban(&self, user_id: i32, ban: bool) -> Result<bool>;
+
+    async fn password_migration_report(&self) -> Result<PasswordMigrationReport>;
+}
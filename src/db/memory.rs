@@ -0,0 +1,111 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{TimeDelta, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use super::account::AccountRepository;
+use crate::model::password::is_hashed;
+use crate::model::user::{PasswordMigrationReport, User};
+
+/// Tài khoản mẫu cho test: thuộc server 1, không phải admin, không bị khóa, mật khẩu rỗng,
+/// login / logout lần cuối từ hôm trước nên không vướng `second_wait_login`.
+/// Đổi field khác bằng `User { password: ..., ..account(1, "player") }`
+pub fn account(id: i32, username: &str) -> User {
+    let yesterday = Utc::now() - TimeDelta::days(1);
+    User {
+        id,
+        username: username.to_string(),
+        is_admin: false,
+        active: true,
+        thoi_vang: 0,
+        vnd: 0,
+        tongnap: 0,
+        server_login: 1,
+        last_time_login: yesterday,
+        last_time_logout: yesterday,
+        reward: None,
+        ban: false,
+        password: String::new(),
+    }
+}
+
+/// Lưu tài khoản trong bộ nhớ, dùng cho test không cần MySQL
+#[derive(Clone, Default)]
+pub struct MemoryAccountRepository {
+    accounts: Arc<RwLock<HashMap<i32, User>>>,
+}
+
+impl MemoryAccountRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Thêm hoặc thay thế tài khoản theo `id`
+    pub async fn insert(&self, user: User) {
+        let mut accounts = self.accounts.write().await;
+        accounts.insert(user.id, user);
+    }
+
+    pub async fn get(&self, user_id: i32) -> Option<User> {
+        let accounts = self.accounts.read().await;
+        accounts.get(&user_id).cloned()
+    }
+
+    /// Trả về `false` nếu không có tài khoản `user_id`
+    async fn update(&self, user_id: i32, f: impl FnOnce(&mut User)) -> bool {
+        let mut accounts = self.accounts.write().await;
+        match accounts.get_mut(&user_id) {
+            Some(user) => {
+                f(user);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[async_trait]
+impl AccountRepository for MemoryAccountRepository {
+    async fn find_by_username(&self, username: &str) -> Result<Option<User>> {
+        let accounts = self.accounts.read().await;
+        Ok(accounts
+            .values()
+            .find(|user| user.username == username)
+            .cloned())
+    }
+
+    async fn update_password_hash(&self, user_id: i32, hash: &str) -> Result<()> {
+        self.update(user_id, |user| user.password = hash.to_string())
+            .await;
+        Ok(())
+    }
+
+    async fn update_login_time(&self, user_id: i32) -> Result<()> {
+        self.update(user_id, |user| user.last_time_login = Utc::now())
+            .await;
+        Ok(())
+    }
+
+    async fn update_logout_time(&self, user_id: i32) -> Result<()> {
+        self.update(user_id, |user| user.last_time_logout = Utc::now())
+            .await;
+        Ok(())
+    }
+
+    async fn set_ban(&self, user_id: i32, ban: bool) -> Result<bool> {
+        Ok(self.update(user_id, |user| user.ban = ban).await)
+    }
+
+    async fn password_migration_report(&self) -> Result<PasswordMigrationReport> {
+        let accounts = self.accounts.read().await;
+        Ok(PasswordMigrationReport {
+            total: accounts.len() as i64,
+            plaintext: accounts
+                .values()
+                .filter(|user| !is_hashed(&user.password))
+                .count() as i64,
+        })
+    }
+}
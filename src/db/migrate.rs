@@ -0,0 +1,101 @@
+use anyhow::Result;
+use sqlx::migrate::{AppliedMigration, Migrate, Migrator};
+
+use super::DbPool;
+
+/// Migration nhúng vào binary, thư mục theo driver
+static MYSQL: Migrator = sqlx::migrate!("migrations/mysql");
+#[cfg(feature = "postgres")]
+static POSTGRES: Migrator = sqlx::migrate!("migrations/postgres");
+
+/// Trạng thái của một migration so với database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    /// Đã chạy nhưng file SQL đã bị sửa sau đó
+    pub checksum_mismatch: bool,
+}
+
+/// Chạy mọi migration chưa được áp dụng
+pub async fn run(pool: &DbPool) -> Result<()> {
+    match pool {
+        DbPool::MySql(pool) => MYSQL.run(pool).await?,
+        #[cfg(feature = "postgres")]
+        DbPool::Postgres(pool) => POSTGRES.run(pool).await?,
+    }
+    Ok(())
+}
+
+pub async fn status(pool: &DbPool) -> Result<Vec<MigrationStatus>> {
+    let (migrator, applied) = match pool {
+        DbPool::MySql(pool) => {
+            let mut conn = pool.acquire().await?;
+            conn.ensure_migrations_table().await?;
+            (&MYSQL, conn.list_applied_migrations().await?)
+        }
+        #[cfg(feature = "postgres")]
+        DbPool::Postgres(pool) => {
+            let mut conn = pool.acquire().await?;
+            conn.ensure_migrations_table().await?;
+            (&POSTGRES, conn.list_applied_migrations().await?)
+        }
+    };
+    Ok(merge_status(migrator, &applied))
+}
+
+fn merge_status(migrator: &Migrator, applied: &[AppliedMigration]) -> Vec<MigrationStatus> {
+    migrator
+        .iter()
+        .map(|migration| {
+            let applied = applied.iter().find(|a| a.version == migration.version);
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied.is_some(),
+                checksum_mismatch: applied.is_some_and(|a| a.checksum != migration.checksum),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[test]
+    fn embedded_migrations_are_ordered() {
+        let versions: Vec<i64> = MYSQL.iter().map(|m| m.version).collect();
+        assert!(!versions.is_empty());
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(
+            MYSQL
+                .iter()
+                .any(|m| m.sql.contains("password VARCHAR(255)"))
+        );
+    }
+
+    #[test]
+    fn status_marks_applied_and_modified_migrations() {
+        let first = MYSQL.iter().next().unwrap();
+        let second = MYSQL.iter().nth(1).unwrap();
+        let applied = [
+            AppliedMigration {
+                version: first.version,
+                checksum: first.checksum.clone(),
+            },
+            AppliedMigration {
+                version: second.version,
+                checksum: Cow::Owned(vec![0; 48]),
+            },
+        ];
+
+        let status = merge_status(&MYSQL, &applied);
+        assert_eq!(status.len(), MYSQL.iter().count());
+        assert!(status[0].applied && !status[0].checksum_mismatch);
+        assert!(status[1].applied && status[1].checksum_mismatch);
+        assert!(status[2..].iter().all(|s| !s.applied));
+    }
+}
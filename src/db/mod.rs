@@ -1,29 +1,171 @@
-use crate::config::DatabaseConfig;
+pub mod account;
+pub mod memory;
+pub mod migrate;
+pub mod mysql;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod schema;
+
+use crate::config::{DatabaseConfig, DatabaseDriver, LoginHistoryConfig};
 use anyhow::Result;
-use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
+use sqlx::mysql::{MySqlConnectOptions, MySqlPool, MySqlPoolOptions};
+#[cfg(feature = "postgres")]
+use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions};
+use std::sync::Arc;
+
+use account::AccountRepository;
+
+/// Connection pool theo `driver` trong `[database]`
+#[derive(Debug, Clone)]
+pub enum DbPool {
+    MySql(MySqlPool),
+    #[cfg(feature = "postgres")]
+    Postgres(PgPool),
+}
+
+/// Tham số kết nối truyền thẳng từng trường, không ghép URL nên mật khẩu
+/// có `@`, `/`, `:` hay `%` không cần escape
+fn mysql_options(config: &DatabaseConfig) -> MySqlConnectOptions {
+    MySqlConnectOptions::new()
+        .host(&config.host)
+        .port(config.port)
+        .username(&config.username)
+        .password(&config.password)
+        .database(&config.database_name)
+}
+
+#[cfg(feature = "postgres")]
+fn postgres_options(config: &DatabaseConfig) -> PgConnectOptions {
+    PgConnectOptions::new()
+        .host(&config.host)
+        .port(config.port)
+        .username(&config.username)
+        .password(&config.password)
+        .database(&config.database_name)
+}
+
+/// Số kết nối của pool tại một thời điểm
+#[derive(Debug, Clone, Copy)]
+pub struct PoolUsage {
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+}
+
+impl DbPool {
+    pub fn usage(&self) -> PoolUsage {
+        match self {
+            DbPool::MySql(pool) => PoolUsage {
+                size: pool.size(),
+                idle: pool.num_idle(),
+                max: pool.options().get_max_connections(),
+            },
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => PoolUsage {
+                size: pool.size(),
+                idle: pool.num_idle(),
+                max: pool.options().get_max_connections(),
+            },
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct DbManager {
-    pool: MySqlPool,
+    pool: DbPool,
 }
 impl DbManager {
-    pub async fn new(config: &DatabaseConfig) -> Result<Self> {
-        let database_url = format!(
-            "mysql://{}:{}@{}:{}/{}",
-            config.username, config.password, config.host, config.port, config.database_name
-        );
-        let pool = MySqlPoolOptions::new()
-            .min_connections(config.min_connections)
-            .max_connections(config.max_connections)
-            .connect(&database_url)
-            .await?;
+    /// Kết nối, chạy migration nếu bật `auto_migrate` rồi kiểm tra schema bảng `account`
+    /// và bảng `login_history` nếu lịch sử đăng nhập được bật
+    pub async fn new(config: &DatabaseConfig, login_history: &LoginHistoryConfig) -> Result<Self> {
+        let db = Self::connect(config).await?;
+        if config.auto_migrate {
+            migrate::run(db.get_pool()).await?;
+            tracing::info!("Database migrations applied");
+        }
+        if let Err(e) = schema::validate(db.get_pool(), login_history.enabled).await {
+            db.close().await;
+            return Err(e.context("database schema check failed"));
+        }
+        Ok(db)
+    }
+    /// Chỉ kết nối, không kiểm tra schema (dùng cho lệnh `migrate`)
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self> {
+        let pool = match config.driver {
+            DatabaseDriver::Mysql => {
+                let pool = MySqlPoolOptions::new()
+                    .min_connections(config.min_connections)
+                    .max_connections(config.max_connections)
+                    .connect_with(mysql_options(config))
+                    .await?;
+                DbPool::MySql(pool)
+            }
+            #[cfg(feature = "postgres")]
+            DatabaseDriver::Postgres => {
+                let pool = PgPoolOptions::new()
+                    .min_connections(config.min_connections)
+                    .max_connections(config.max_connections)
+                    .connect_with(postgres_options(config))
+                    .await?;
+                DbPool::Postgres(pool)
+            }
+            #[cfg(not(feature = "postgres"))]
+            DatabaseDriver::Postgres => {
+                anyhow::bail!(
+                    "database.driver = \"postgres\" requires building with `--features postgres`"
+                )
+            }
+        };
         Ok(Self { pool })
     }
-    pub fn get_pool(&self) -> &MySqlPool {
+    pub fn get_pool(&self) -> &DbPool {
         &self.pool
     }
+    /// Account store tương ứng với driver đang dùng
+    pub fn accounts(&self) -> Arc<dyn AccountRepository> {
+        match &self.pool {
+            DbPool::MySql(pool) => Arc::new(mysql::MySqlAccountRepository::new(pool.clone())),
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => Arc::new(postgres::PgAccountRepository::new(pool.clone())),
+        }
+    }
     pub async fn close(&self) {
-        self.pool.close().await;
+        match &self.pool {
+            DbPool::MySql(pool) => pool.close().await,
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => pool.close().await,
+        }
         println!("Db Connection Pool is shutting down")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn database(password: &str) -> DatabaseConfig {
+        let mut database = testing::config().database;
+        database.host = "db.internal".to_string();
+        database.port = 3307;
+        database.username = "game@login".to_string();
+        database.password = password.to_string();
+        database
+    }
+
+    #[test]
+    fn special_characters_do_not_break_connect_options() {
+        let password = "p@ss/w:rd%40#?";
+        let config = database(password);
+        let options = mysql_options(&config);
+        assert_eq!(options.get_host(), "db.internal");
+        assert_eq!(options.get_port(), 3307);
+        assert_eq!(options.get_username(), "game@login");
+        assert_eq!(options.get_database(), Some("nro"));
+        // sqlx không có getter cho mật khẩu, kiểm tra qua Debug
+        let stored = format!("password: Some({:?})", password);
+        assert!(format!("{:?}", options).contains(&stored));
+        #[cfg(feature = "postgres")]
+        assert!(format!("{:?}", postgres_options(&config)).contains(&stored));
+    }
+}
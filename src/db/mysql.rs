@@ -0,0 +1,78 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::MySqlPool;
+
+use super::account::AccountRepository;
+use super::schema::ACCOUNT_SELECT;
+use crate::model::user::{PasswordMigrationReport, User};
+
+/// Bảng `account` trên MySQL
+#[derive(Debug, Clone)]
+pub struct MySqlAccountRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlAccountRepository {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AccountRepository for MySqlAccountRepository {
+    async fn find_by_username(&self, username: &str) -> Result<Option<User>> {
+        let sql = format!(
+            "SELECT {} FROM account WHERE username = ? LIMIT 1",
+            *ACCOUNT_SELECT
+        );
+        let user = sqlx::query_as::<_, User>(&sql)
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(user)
+    }
+
+    async fn update_password_hash(&self, user_id: i32, hash: &str) -> Result<()> {
+        sqlx::query("UPDATE account SET password = ? WHERE id = ?")
+            .bind(hash)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_login_time(&self, user_id: i32) -> Result<()> {
+        sqlx::query("UPDATE account SET last_time_login = NOW() WHERE id = ?")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_logout_time(&self, user_id: i32) -> Result<()> {
+        sqlx::query("UPDATE account SET last_time_logout = NOW() WHERE id = ?")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_ban(&self, user_id: i32, ban: bool) -> Result<bool> {
+        let result = sqlx::query("UPDATE account SET ban = ? WHERE id = ?")
+            .bind(ban)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn password_migration_report(&self) -> Result<PasswordMigrationReport> {
+        let (total, plaintext): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), CAST(COALESCE(SUM(password NOT LIKE '$argon2%'), 0) AS SIGNED) \
+             FROM account",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(PasswordMigrationReport { total, plaintext })
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use super::account::AccountRepository;
+use super::schema::ACCOUNT_SELECT;
+use crate::model::user::{PasswordMigrationReport, User};
+
+/// Bảng `account` trên PostgreSQL (bật bằng feature `postgres`)
+#[derive(Debug, Clone)]
+pub struct PgAccountRepository {
+    pool: PgPool,
+}
+
+impl PgAccountRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AccountRepository for PgAccountRepository {
+    async fn find_by_username(&self, username: &str) -> Result<Option<User>> {
+        let sql = format!(
+            "SELECT {} FROM account WHERE username = $1 LIMIT 1",
+            *ACCOUNT_SELECT
+        );
+        let user = sqlx::query_as::<_, User>(&sql)
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(user)
+    }
+
+    async fn update_password_hash(&self, user_id: i32, hash: &str) -> Result<()> {
+        sqlx::query("UPDATE account SET password = $1 WHERE id = $2")
+            .bind(hash)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_login_time(&self, user_id: i32) -> Result<()> {
+        sqlx::query("UPDATE account SET last_time_login = NOW() WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_logout_time(&self, user_id: i32) -> Result<()> {
+        sqlx::query("UPDATE account SET last_time_logout = NOW() WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_ban(&self, user_id: i32, ban: bool) -> Result<bool> {
+        let result = sqlx::query("UPDATE account SET ban = $1 WHERE id = $2")
+            .bind(ban)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn password_migration_report(&self) -> Result<PasswordMigrationReport> {
+        let (total, plaintext): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), COUNT(*) FILTER (WHERE password NOT LIKE '$argon2%') FROM account",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(PasswordMigrationReport { total, plaintext })
+    }
+}
@@ -0,0 +1,399 @@
+use anyhow::Result;
+use std::fmt;
+use std::sync::LazyLock;
+
+use super::DbPool;
+
+/// Kiểu Rust mà `User` decode, mỗi loại chấp nhận một số kiểu cột
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Int,
+    Bool,
+    Text,
+    Timestamp,
+}
+
+impl ColumnKind {
+    fn mysql_types(&self) -> &'static [&'static str] {
+        match self {
+            ColumnKind::Int => &["int", "mediumint", "smallint"],
+            ColumnKind::Bool => &["tinyint", "bit"],
+            ColumnKind::Text => &[
+                "varchar",
+                "char",
+                "text",
+                "tinytext",
+                "mediumtext",
+                "longtext",
+            ],
+            ColumnKind::Timestamp => &["datetime", "timestamp"],
+        }
+    }
+
+    #[cfg(feature = "postgres")]
+    fn postgres_types(&self) -> &'static [&'static str] {
+        match self {
+            ColumnKind::Int => &["integer"],
+            ColumnKind::Bool => &["boolean"],
+            ColumnKind::Text => &["character varying", "character", "text"],
+            ColumnKind::Timestamp => &["timestamp with time zone"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnSpec {
+    pub name: &'static str,
+    pub kind: ColumnKind,
+    pub nullable: bool,
+}
+
+const fn column(name: &'static str, kind: ColumnKind) -> ColumnSpec {
+    ColumnSpec {
+        name,
+        kind,
+        nullable: false,
+    }
+}
+
+/// Các cột của bảng `account` mà `User` đọc, theo đúng thứ tự field
+pub const ACCOUNT_COLUMNS: &[ColumnSpec] = &[
+    column("id", ColumnKind::Int),
+    column("username", ColumnKind::Text),
+    column("is_admin", ColumnKind::Bool),
+    column("active", ColumnKind::Bool),
+    column("thoi_vang", ColumnKind::Int),
+    column("vnd", ColumnKind::Int),
+    column("tongnap", ColumnKind::Int),
+    column("server_login", ColumnKind::Int),
+    column("last_time_login", ColumnKind::Timestamp),
+    column("last_time_logout", ColumnKind::Timestamp),
+    ColumnSpec {
+        name: "reward",
+        kind: ColumnKind::Text,
+        nullable: true,
+    },
+    column("ban", ColumnKind::Bool),
+    column("password", ColumnKind::Text),
+];
+
+/// Các cột của bảng `login_history` mà writer INSERT
+pub const LOGIN_HISTORY_COLUMNS: &[ColumnSpec] = &[
+    ColumnSpec {
+        name: "account_id",
+        kind: ColumnKind::Int,
+        nullable: true,
+    },
+    column("username", ColumnKind::Text),
+    column("server_id", ColumnKind::Int),
+    column("client_id", ColumnKind::Int),
+    column("peer_addr", ColumnKind::Text),
+    column("result", ColumnKind::Text),
+    column("created_at", ColumnKind::Timestamp),
+];
+
+/// `id, username, ...` dùng trong SELECT thay cho `*`
+pub static ACCOUNT_SELECT: LazyLock<String> = LazyLock::new(|| {
+    ACCOUNT_COLUMNS
+        .iter()
+        .map(|column| column.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+});
+
+/// Một cột đọc từ `information_schema.columns`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+    /// `COLUMN_TYPE` có `unsigned` (chỉ MySQL), sqlx không decode được vào `i32`
+    pub unsigned: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMismatch {
+    pub name: &'static str,
+    pub expected: String,
+    pub found: String,
+}
+
+/// Kết quả so sánh schema thực tế với `ACCOUNT_COLUMNS`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaReport {
+    pub table: &'static str,
+    pub table_missing: bool,
+    pub missing: Vec<&'static str>,
+    pub mismatched: Vec<ColumnMismatch>,
+}
+
+impl SchemaReport {
+    pub fn is_ok(&self) -> bool {
+        !self.table_missing && self.missing.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.table_missing {
+            return write!(
+                f,
+                "table `{}` does not exist (run `login_server_rust migrate up`)",
+                self.table
+            );
+        }
+        write!(
+            f,
+            "table `{}` does not match the expected schema:",
+            self.table
+        )?;
+        for name in &self.missing {
+            write!(f, "\n  - missing column `{}`", name)?;
+        }
+        for mismatch in &self.mismatched {
+            write!(
+                f,
+                "\n  - column `{}`: expected {}, found {}",
+                mismatch.name, mismatch.expected, mismatch.found
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Kiểm tra bảng `account` (và `login_history` nếu `login_history` bật),
+/// trả lỗi mô tả các cột thiếu / sai kiểu
+pub async fn validate(pool: &DbPool, login_history: bool) -> Result<()> {
+    validate_table(pool, "account", ACCOUNT_COLUMNS).await?;
+    if login_history {
+        validate_table(pool, "login_history", LOGIN_HISTORY_COLUMNS).await?;
+    }
+    Ok(())
+}
+
+async fn validate_table(pool: &DbPool, table: &'static str, expected: &[ColumnSpec]) -> Result<()> {
+    let report = match pool {
+        DbPool::MySql(pool) => {
+            let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+                "SELECT CAST(COLUMN_NAME AS CHAR), CAST(DATA_TYPE AS CHAR), \
+                 CAST(IS_NULLABLE AS CHAR), CAST(COLUMN_TYPE AS CHAR) \
+                 FROM information_schema.COLUMNS \
+                 WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?",
+            )
+            .bind(table)
+            .fetch_all(pool)
+            .await?;
+            compare(table, expected, &to_columns(rows), ColumnKind::mysql_types)
+        }
+        #[cfg(feature = "postgres")]
+        DbPool::Postgres(pool) => {
+            let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+                "SELECT column_name::text, data_type::text, is_nullable::text, data_type::text \
+                 FROM information_schema.columns \
+                 WHERE table_schema = current_schema() AND table_name = $1",
+            )
+            .bind(table)
+            .fetch_all(pool)
+            .await?;
+            compare(
+                table,
+                expected,
+                &to_columns(rows),
+                ColumnKind::postgres_types,
+            )
+        }
+    };
+    if report.is_ok() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("{}", report))
+    }
+}
+
+fn to_columns(rows: Vec<(String, String, String, String)>) -> Vec<ColumnInfo> {
+    rows.into_iter()
+        .map(|(name, data_type, nullable, column_type)| ColumnInfo {
+            name,
+            data_type: data_type.to_lowercase(),
+            nullable: nullable.eq_ignore_ascii_case("YES"),
+            unsigned: column_type.to_lowercase().contains("unsigned"),
+        })
+        .collect()
+}
+
+fn compare(
+    table: &'static str,
+    expected: &[ColumnSpec],
+    found: &[ColumnInfo],
+    accepted_types: fn(&ColumnKind) -> &'static [&'static str],
+) -> SchemaReport {
+    let mut report = SchemaReport {
+        table,
+        table_missing: found.is_empty(),
+        ..SchemaReport::default()
+    };
+    if report.table_missing {
+        return report;
+    }
+    for spec in expected {
+        let Some(column) = found
+            .iter()
+            .find(|column| column.name.eq_ignore_ascii_case(spec.name))
+        else {
+            report.missing.push(spec.name);
+            continue;
+        };
+        let types = accepted_types(&spec.kind);
+        let type_ok = types.contains(&column.data_type.as_str())
+            && !(spec.kind == ColumnKind::Int && column.unsigned);
+        let null_ok = spec.nullable || !column.nullable;
+        if !type_ok || !null_ok {
+            let describe = |data_type: &str, unsigned: bool, nullable: bool| {
+                format!(
+                    "{}{}{}",
+                    data_type,
+                    if unsigned { " unsigned" } else { "" },
+                    if nullable { " NULL" } else { " NOT NULL" }
+                )
+            };
+            report.mismatched.push(ColumnMismatch {
+                name: spec.name,
+                expected: describe(&types.join("/"), false, spec.nullable),
+                found: describe(&column.data_type, column.unsigned, column.nullable),
+            });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mysql_column(name: &str, data_type: &str, nullable: bool) -> ColumnInfo {
+        ColumnInfo {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable,
+            unsigned: false,
+        }
+    }
+
+    fn migrated_account() -> Vec<ColumnInfo> {
+        ACCOUNT_COLUMNS
+            .iter()
+            .map(|spec| {
+                let data_type = match spec.kind {
+                    ColumnKind::Int => "int",
+                    ColumnKind::Bool => "tinyint",
+                    ColumnKind::Text => "varchar",
+                    ColumnKind::Timestamp => "datetime",
+                };
+                mysql_column(spec.name, data_type, spec.nullable)
+            })
+            .chain([mysql_column("create_time", "datetime", true)])
+            .collect()
+    }
+
+    #[test]
+    fn select_list_has_every_user_column() {
+        assert!(ACCOUNT_SELECT.starts_with("id, username, is_admin"));
+        assert!(ACCOUNT_SELECT.ends_with("ban, password"));
+    }
+
+    #[test]
+    fn matching_schema_with_extra_columns_passes() {
+        let report = compare(
+            "account",
+            ACCOUNT_COLUMNS,
+            &migrated_account(),
+            ColumnKind::mysql_types,
+        );
+        assert!(report.is_ok(), "{}", report);
+    }
+
+    #[test]
+    fn drift_is_reported_per_column() {
+        let mut columns = migrated_account();
+        columns.retain(|c| c.name != "ban");
+        for column in &mut columns {
+            match column.name.as_str() {
+                "thoi_vang" => column.data_type = "bigint".to_string(),
+                "last_time_logout" => column.nullable = true,
+                "server_login" => column.unsigned = true,
+                _ => {}
+            }
+        }
+
+        let report = compare(
+            "account",
+            ACCOUNT_COLUMNS,
+            &columns,
+            ColumnKind::mysql_types,
+        );
+        assert_eq!(report.missing, vec!["ban"]);
+        let names: Vec<_> = report.mismatched.iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["thoi_vang", "server_login", "last_time_logout"]);
+
+        let text = report.to_string();
+        assert!(text.contains("missing column `ban`"), "{}", text);
+        assert!(
+            text.contains("column `thoi_vang`: expected int/mediumint/smallint NOT NULL, found bigint NOT NULL"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("column `server_login`: expected int/mediumint/smallint NOT NULL, found int unsigned NOT NULL"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("column `last_time_logout`: expected datetime/timestamp NOT NULL, found datetime NULL"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn login_history_table_is_checked_like_account() {
+        let columns: Vec<ColumnInfo> = [
+            ("id", "bigint", false),
+            ("account_id", "int", true),
+            ("username", "varchar", false),
+            ("server_id", "int", false),
+            ("client_id", "int", false),
+            ("peer_addr", "varchar", false),
+            ("result", "varchar", false),
+            ("created_at", "datetime", false),
+        ]
+        .into_iter()
+        .map(|(name, data_type, nullable)| mysql_column(name, data_type, nullable))
+        .collect();
+        let report = compare(
+            "login_history",
+            LOGIN_HISTORY_COLUMNS,
+            &columns,
+            ColumnKind::mysql_types,
+        );
+        assert!(report.is_ok(), "{}", report);
+
+        let report = compare(
+            "login_history",
+            LOGIN_HISTORY_COLUMNS,
+            &[],
+            ColumnKind::mysql_types,
+        );
+        assert!(
+            report
+                .to_string()
+                .contains("`login_history` does not exist")
+        );
+    }
+
+    #[test]
+    fn missing_table_suggests_migration() {
+        let report = compare("account", ACCOUNT_COLUMNS, &[], ColumnKind::mysql_types);
+        assert!(report.table_missing);
+        assert!(report.to_string().contains("migrate up"));
+    }
+}
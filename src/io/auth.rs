@@ -0,0 +1,96 @@
+//! Xác thực game server bằng challenge-response sau khi trao đổi key (-27):
+//!
+//! 1. Login server gửi `AUTH_CHALLENGE` chứa nonce ngẫu nhiên.
+//! 2. Game server trả `AUTH_RESPONSE` gồm server_id và
+//!    `HMAC-SHA256(secret, nonce || server_id)` (server_id dạng int big-endian).
+//! 3. Login server kiểm tra với secret của server_id trong `config.toml` và trả `AUTH_RESULT`.
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use sha2::Sha256;
+
+use crate::config::AuthConfig;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const NONCE_LEN: usize = 32;
+
+#[derive(Debug, Clone)]
+pub struct Authenticator {
+    config: AuthConfig,
+}
+
+impl Authenticator {
+    /// Trả về `None` khi tắt xác thực (`[auth] enabled = false`)
+    pub fn from_config(config: &AuthConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            config: config.clone(),
+        })
+    }
+
+    pub fn new_nonce() -> Vec<u8> {
+        let mut nonce = vec![0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        nonce
+    }
+
+    /// Kiểm tra MAC của game server (so sánh constant-time)
+    pub fn verify(&self, server_id: i32, nonce: &[u8], mac: &[u8]) -> bool {
+        let Some(secret) = self.config.secret_for(server_id) else {
+            return false;
+        };
+        let Ok(mut hmac) = HmacSha256::new_from_slice(secret.as_bytes()) else {
+            return false;
+        };
+        hmac.update(nonce);
+        hmac.update(&server_id.to_be_bytes());
+        hmac.verify_slice(mac).is_ok()
+    }
+}
+
+/// Tính MAC phía game server, dùng cho tool và test
+pub fn sign(secret: &str, server_id: i32, nonce: &[u8]) -> Vec<u8> {
+    let mut hmac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    hmac.update(nonce);
+    hmac.update(&server_id.to_be_bytes());
+    hmac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn authenticator() -> Authenticator {
+        let config = AuthConfig {
+            enabled: true,
+            secrets: HashMap::from([("1".to_string(), "secret-sv1".to_string())]),
+        };
+        Authenticator::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn disabled_config_has_no_authenticator() {
+        assert!(Authenticator::from_config(&AuthConfig::default()).is_none());
+    }
+
+    #[test]
+    fn accepts_valid_mac() {
+        let nonce = Authenticator::new_nonce();
+        let mac = sign("secret-sv1", 1, &nonce);
+        assert!(authenticator().verify(1, &nonce, &mac));
+    }
+
+    #[test]
+    fn rejects_wrong_secret_server_or_nonce() {
+        let auth = authenticator();
+        let nonce = Authenticator::new_nonce();
+        assert!(!auth.verify(1, &nonce, &sign("wrong", 1, &nonce)));
+        // MAC của server 1 không dùng được để đăng ký làm server 2
+        assert!(!auth.verify(2, &nonce, &sign("secret-sv1", 1, &nonce)));
+        let other = Authenticator::new_nonce();
+        assert!(!auth.verify(1, &other, &sign("secret-sv1", 1, &nonce)));
+    }
+}
@@ -0,0 +1,315 @@
+use bytes::{Buf, BufMut, BytesMut};
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::message::{Message, ProtocolError};
+use crate::command;
+
+/// Key cố định của các bản game server cũ
+pub const LEGACY_KEY: &[u8] = b"vmn";
+
+/// Sinh key XOR ngẫu nhiên (CSPRNG) cho một session, độ dài 8-16 byte
+pub fn generate_key() -> Vec<u8> {
+    let len = OsRng.gen_range(8..=16);
+    let mut key = vec![0u8; len];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+/// Codec cho frame `[cmd:1][size:2][data:size]`, XOR với key sau khi đã gửi key (-27).
+///
+/// `key_sent` được chia sẻ giữa các bản clone, nên có thể tách một bản cho phía đọc và
+/// một bản cho phía ghi: khi phía ghi gửi key xong thì phía đọc cũng chuyển sang giải mã.
+#[derive(Debug, Clone)]
+pub struct MessageCodec {
+    key: Arc<[u8]>,
+    key_sent: Arc<AtomicBool>,
+    cur_r: usize,
+    cur_w: usize,
+}
+
+impl MessageCodec {
+    pub fn new(key: Vec<u8>) -> Self {
+        assert!(!key.is_empty(), "XOR key must not be empty");
+        Self {
+            key: key.into(),
+            key_sent: Arc::new(AtomicBool::new(false)),
+            cur_r: 0,
+            cur_w: 0,
+        }
+    }
+
+    /// Tạo codec phía game server từ gói -27 nhận được (đã ở trạng thái mã hóa)
+    pub fn from_key_message(msg: &mut Message) -> Result<Self, ProtocolError> {
+        let len = msg.read_byte()? as u8 as usize;
+        let mut key = Vec::with_capacity(len);
+        for i in 0..len {
+            let b = msg.read_byte()? as u8;
+            key.push(if i == 0 { b } else { b ^ key[i - 1] });
+        }
+        if key.is_empty() {
+            return Err(ProtocolError::Truncated {
+                field: "key",
+                offset: 1,
+                expected: 1,
+                available: 0,
+            });
+        }
+        let codec = Self::new(key);
+        codec.key_sent.store(true, Ordering::Release);
+        Ok(codec)
+    }
+
+    pub fn is_key_sent(&self) -> bool {
+        self.key_sent.load(Ordering::Acquire)
+    }
+
+    /// Gói tin -27 gửi key cho game server (byte đầu giữ nguyên, các byte sau XOR với byte trước)
+    pub fn key_message(&self) -> Message {
+        let mut msg = Message::new(command::SEND_KEY);
+        msg.write_byte(self.key.len() as i8);
+        msg.write_byte(self.key[0] as i8);
+        for i in 1..self.key.len() {
+            msg.write_byte((self.key[i] ^ self.key[i - 1]) as i8);
+        }
+        msg
+    }
+
+    fn read_key(&self, cur: &mut usize, b: u8) -> u8 {
+        let result = self.key[*cur] ^ b;
+        *cur = (*cur + 1) % self.key.len();
+        result
+    }
+
+    fn write_key(&mut self, b: u8) -> u8 {
+        let result = self.key[self.cur_w] ^ b;
+        self.cur_w = (self.cur_w + 1) % self.key.len();
+        result
+    }
+}
+
+impl Decoder for MessageCodec {
+    type Item = Message;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Message>, io::Error> {
+        if src.len() < 3 {
+            return Ok(None);
+        }
+        let encrypted = self.is_key_sent();
+
+        // Giải mã header bằng cursor tạm, chỉ lưu lại cursor khi đã đủ cả frame
+        let mut cur = self.cur_r;
+        let (cmd, size) = if encrypted {
+            let cmd = self.read_key(&mut cur, src[0]);
+            let b1 = self.read_key(&mut cur, src[1]);
+            let b2 = self.read_key(&mut cur, src[2]);
+            (cmd, u16::from_be_bytes([b1, b2]) as usize)
+        } else {
+            (src[0], u16::from_be_bytes([src[1], src[2]]) as usize)
+        };
+
+        if src.len() < 3 + size {
+            src.reserve(3 + size - src.len());
+            return Ok(None);
+        }
+        src.advance(3);
+        let mut data = src.split_to(size).to_vec();
+        if encrypted {
+            for byte in data.iter_mut() {
+                *byte = self.read_key(&mut cur, *byte);
+            }
+            self.cur_r = cur;
+        }
+        Ok(Some(Message::with_data(cmd as i8, data)))
+    }
+}
+
+impl Encoder<Message> for MessageCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, msg: Message, dst: &mut BytesMut) -> Result<(), io::Error> {
+        Encoder::<&Message>::encode(self, &msg, dst)
+    }
+}
+
+impl Encoder<&Message> for MessageCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, msg: &Message, dst: &mut BytesMut) -> Result<(), io::Error> {
+        let data = msg.get_data();
+        let size = u16::try_from(data.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("message too large: {} bytes", data.len()),
+            )
+        })?;
+        dst.reserve(3 + data.len());
+
+        if !self.is_key_sent() {
+            dst.put_u8(msg.command as u8);
+            dst.put_u16(size);
+            dst.put_slice(data);
+            if msg.command == command::SEND_KEY {
+                self.key_sent.store(true, Ordering::Release);
+            }
+            return Ok(());
+        }
+
+        let [b1, b2] = size.to_be_bytes();
+        let header = [
+            self.write_key(msg.command as u8),
+            self.write_key(b1),
+            self.write_key(b2),
+        ];
+        dst.put_slice(&header);
+        for &byte in data {
+            let encrypted = self.write_key(byte);
+            dst.put_u8(encrypted);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(codec: &mut MessageCodec, msg: &Message) -> BytesMut {
+        let mut buf = BytesMut::new();
+        codec.encode(msg, &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn plain_frame_before_key_exchange() {
+        let mut codec = MessageCodec::new(LEGACY_KEY.to_vec());
+        let mut msg = Message::new(command::LOGOUT);
+        msg.write_int(5);
+        let mut buf = encode(&mut codec, &msg);
+        assert_eq!(&buf[..], &[2, 0, 4, 0, 0, 0, 5]);
+
+        let mut decoded = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(decoded.command, command::LOGOUT);
+        assert_eq!(decoded.read_int().unwrap(), 5);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn key_message_layout() {
+        let codec = MessageCodec::new(LEGACY_KEY.to_vec());
+        let msg = codec.key_message();
+        assert_eq!(msg.command, command::SEND_KEY);
+        assert_eq!(msg.get_data(), &[3, b'v', b'v' ^ b'm', b'm' ^ b'n']);
+    }
+
+    #[test]
+    fn frames_are_xored_after_key_is_sent() {
+        let mut server = MessageCodec::new(LEGACY_KEY.to_vec());
+        let key = server.key_message();
+        let key_frame = encode(&mut server, &key);
+        assert_eq!(key_frame[0], command::SEND_KEY as u8);
+        assert!(server.is_key_sent());
+
+        let mut msg = Message::new(command::DISCONNECT);
+        msg.write_int(42);
+        let buf = encode(&mut server, &msg);
+        let expected: Vec<u8> = [3u8, 0, 4, 0, 0, 0, 42]
+            .iter()
+            .zip(b"vmn".iter().cycle())
+            .map(|(b, k)| b ^ k)
+            .collect();
+        assert_eq!(&buf[..], &expected[..]);
+    }
+
+    #[test]
+    fn encrypted_round_trip_keeps_cursors_in_sync() {
+        // Phía game server: key đã biết, decode những gì server gửi và ngược lại
+        let mut server = MessageCodec::new(b"abcd".to_vec());
+        let key = server.key_message();
+        encode(&mut server, &key);
+        let mut peer = MessageCodec::new(b"abcd".to_vec());
+        peer.key_sent.store(true, Ordering::Release);
+
+        let mut buf = BytesMut::new();
+        for i in 0..3 {
+            let mut msg = Message::new(command::SERVER_MESSAGE);
+            msg.write_int(i);
+            msg.write_utf("xin chào").unwrap();
+            server.encode(&msg, &mut buf).unwrap();
+        }
+        for i in 0..3 {
+            let mut msg = peer.decode(&mut buf).unwrap().unwrap();
+            assert_eq!(msg.command, command::SERVER_MESSAGE);
+            assert_eq!(msg.read_int().unwrap(), i);
+            assert_eq!(msg.read_utf().unwrap(), "xin chào");
+        }
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn partial_frame_does_not_advance_cursor() {
+        let mut writer = MessageCodec::new(b"xyz".to_vec());
+        writer.key_sent.store(true, Ordering::Release);
+        let mut reader = writer.clone();
+
+        let mut msg = Message::new(command::LOGIN);
+        msg.write_long(1);
+        let full = encode(&mut writer, &msg);
+
+        let mut buf = BytesMut::new();
+        for (i, byte) in full.iter().enumerate() {
+            buf.put_u8(*byte);
+            let result = reader.decode(&mut buf).unwrap();
+            if i + 1 < full.len() {
+                assert!(result.is_none());
+            } else {
+                let mut decoded = result.unwrap();
+                assert_eq!(decoded.command, command::LOGIN);
+                assert_eq!(decoded.read_long().unwrap(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn clones_share_key_state() {
+        let mut writer = MessageCodec::new(LEGACY_KEY.to_vec());
+        let reader = writer.clone();
+        let key = writer.key_message();
+        encode(&mut writer, &key);
+        assert!(reader.is_key_sent());
+    }
+
+    #[test]
+    fn generated_keys_are_random() {
+        let a = generate_key();
+        let b = generate_key();
+        assert!((8..=16).contains(&a.len()));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn peer_recovers_random_key_from_key_message() {
+        let key = generate_key();
+        let mut server = MessageCodec::new(key.clone());
+        let mut peer_plain = MessageCodec::new(LEGACY_KEY.to_vec());
+
+        let key_msg = server.key_message();
+        let mut buf = encode(&mut server, &key_msg);
+        let mut received = peer_plain.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(received.command, command::SEND_KEY);
+        let mut peer = MessageCodec::from_key_message(&mut received).unwrap();
+        assert_eq!(&peer.key[..], &key[..]);
+
+        let mut msg = Message::new(command::DISCONNECT);
+        msg.write_int(9);
+        let mut buf = encode(&mut server, &msg);
+        let mut decoded = peer.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(decoded.command, command::DISCONNECT);
+        assert_eq!(decoded.read_int().unwrap(), 9);
+    }
+}
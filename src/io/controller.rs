@@ -1,29 +1,86 @@
+use super::auth::Authenticator;
+use super::link_cleanup::LinkCleanup;
 use super::message::Message;
+use super::packet::{
+    AuthChallenge, AuthResponse, AuthResult, LoginRequest, Logout, Packet, Ping, Pong, ServerSync,
+};
+use super::server_registry::ServerRegistry;
 use super::service::Service;
 use super::session::Session;
-use crate::command::command;
-use crate::config::Config;
-use crate::db::DbManager;
+use crate::command;
+use crate::config::SharedConfig;
+use crate::context::AppContext;
+use crate::db::account::AccountRepository;
+use crate::metrics::Metrics;
+use crate::model::login_history::{LoginAttempt, LoginHistory, LoginResultCode};
+use crate::model::login_limiter::LoginLimiter;
+use crate::model::maintenance::Maintenance;
+use crate::model::password::PasswordHasher;
 use crate::model::user::User;
 use crate::model::user_manager::UserManager;
 use anyhow::Result;
 use chrono::Utc;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
 
 pub struct Controller {
-    db: DbManager,
+    accounts: Arc<dyn AccountRepository>,
     user_manager: UserManager,
-    config: Config,
+    server_registry: ServerRegistry,
+    link_cleanup: LinkCleanup,
+    authenticator: Option<Authenticator>,
+    password_hasher: PasswordHasher,
+    login_limiter: LoginLimiter,
+    maintenance: Maintenance,
+    login_history: LoginHistory,
+    metrics: Metrics,
+    config: SharedConfig,
 }
 
 impl Controller {
-    pub fn new(db: DbManager, user_manager: UserManager, config: Config) -> Self {
+    pub fn new(context: AppContext) -> Self {
+        let AppContext {
+            accounts,
+            user_manager,
+            server_registry,
+            link_cleanup,
+            password_hasher,
+            login_limiter,
+            maintenance,
+            login_history,
+            metrics,
+            config,
+        } = context;
         Self {
-            db,
+            accounts,
             user_manager,
+            server_registry,
+            link_cleanup,
+            authenticator: Authenticator::from_config(&config.load().auth),
+            password_hasher,
+            login_limiter,
+            maintenance,
+            login_history,
+            metrics,
             config,
         }
     }
     pub async fn process(&self, session: &mut Session, msg: Message) -> Result<()> {
+        // Heartbeat không cần xác thực để còn giữ được kết nối khi đang chờ AUTH_RESPONSE
+        match msg.command {
+            command::PING | command::PONG => return self.heartbeat(session, msg).await,
+            _ => {}
+        }
+        if msg.command == command::AUTH_RESPONSE {
+            return self.authenticate(session, msg).await;
+        }
+        if self.authenticator.is_some() && session.authenticated_server().is_none() {
+            warn!(
+                "Session {} ({}) sent command {} before authenticating, rejected",
+                session.id, session.session_name, msg.command
+            );
+            return Ok(());
+        }
         match msg.command {
             command::LOGIN => self.login(session, msg).await?,
             command::LOGOUT => self.logout(session, msg).await?,
@@ -33,24 +90,157 @@ impl Controller {
         Ok(())
     }
 
-    async fn login(&self, session: &mut Session, mut msg: Message) -> Result<()> {
-        let server_id = msg.read_byte()?;
-        let client_id = msg.read_int()?;
-        let username = msg.read_utf()?;
-        let password = msg.read_utf()?;
+    async fn heartbeat(&self, session: &mut Session, mut msg: Message) -> Result<()> {
+        if msg.command == command::PING {
+            let Ping { nonce } = Ping::decode(&mut msg)?;
+            return session.send_packet(&Pong { nonce }).await;
+        }
+        let Pong { nonce } = Pong::decode(&mut msg)?;
+        if let Some(rtt) = session.record_pong(nonce) {
+            debug!("Session {} rtt {:?}", session.id, rtt);
+        }
+        Ok(())
+    }
+
+    /// Gửi AUTH_CHALLENGE ngay sau khi gửi key, không làm gì nếu tắt xác thực
+    pub async fn send_challenge(&self, session: &mut Session) -> Result<()> {
+        if self.authenticator.is_some() && session.authenticated_server().is_none() {
+            let nonce = Authenticator::new_nonce();
+            session.set_auth_nonce(nonce.clone());
+            session.send_packet(&AuthChallenge { nonce }).await?;
+        }
+        Ok(())
+    }
+
+    async fn authenticate(&self, session: &mut Session, mut msg: Message) -> Result<()> {
+        let Some(authenticator) = &self.authenticator else {
+            warn!(
+                "Session {} sent AUTH_RESPONSE but auth is disabled",
+                session.id
+            );
+            return Ok(());
+        };
+        let AuthResponse { server_id, mac } = AuthResponse::decode(&mut msg)?;
+        let verified = session
+            .take_auth_nonce()
+            .is_some_and(|nonce| authenticator.verify(server_id, &nonce, &mac));
+
+        if !verified {
+            warn!(
+                "Session {} ({}) failed authentication as server {}",
+                session.id, session.session_name, server_id
+            );
+            session
+                .send_packet(&AuthResult {
+                    accepted: false,
+                    reason: "Xác thực thất bại".to_string(),
+                })
+                .await?;
+            session.close();
+            return Ok(());
+        }
+
+        info!(
+            "Session {} authenticated as server {}",
+            session.id, server_id
+        );
+        session.set_authenticated_server(server_id);
+        session.set_server_id(server_id);
+        session
+            .send_packet(&AuthResult {
+                accepted: true,
+                reason: String::new(),
+            })
+            .await
+    }
+
+    async fn login(&self, session: &Session, mut msg: Message) -> Result<()> {
+        let authenticated_server = session.authenticated_server();
+        // Counter theo game server dùng server của session, không tin server_id trong gói LOGIN.
+        // Khi tắt xác thực thì dùng server đã đăng ký bằng SET_SERVER (0 là chưa đăng ký)
+        let limiter_server =
+            authenticated_server.or(Some(session.server_id()).filter(|id| *id != 0));
+        let peer_addr = session.session_name.clone();
+        let session = session.handle();
+        let request = match LoginRequest::decode(&mut msg) {
+            Ok(request) => request,
+            Err(e) => {
+                if let Some(client_id) = LoginRequest::peek_client_id(&msg) {
+                    Service::login_failed(session, client_id, "Lỗi hệ thống, vui lòng thử lại!")
+                        .await?;
+                }
+                return Err(e.into());
+            }
+        };
+        let LoginRequest {
+            server_id,
+            client_id,
+            username,
+            password,
+        } = request;
+        let audit = |account_id: Option<i32>, result: LoginResultCode| {
+            self.metrics.record_login(result);
+            self.login_history.record(LoginAttempt {
+                account_id,
+                username: username.clone(),
+                server_id: server_id as i32,
+                client_id,
+                peer_addr: peer_addr.clone(),
+                result,
+                created_at: Utc::now(),
+            });
+        };
+
+        if authenticated_server.is_some_and(|id| id != server_id as i32) {
+            warn!(
+                "Session {} tried to log in user {} on server {}",
+                session.id(),
+                username,
+                server_id
+            );
+            audit(None, LoginResultCode::WrongServer);
+            Service::login_failed(session, client_id, "Lỗi hệ thống, vui lòng thử lại!").await?;
+            return Ok(());
+        }
 
         println!("Login username: {} serverID: {}", username, server_id);
 
-        match User::find_by_credentials(self.db.get_pool(), &username, &password).await {
+        // Check 0: Đang bị khóa do nhập sai nhiều lần, từ chối trước khi truy vấn DB
+        if let Some(wait) = self.login_limiter.check(&username, limiter_server).await {
+            let msg = format!(
+                "Bạn đã nhập sai quá nhiều lần, vui lòng chờ {} giây để đăng nhập lại.",
+                wait.as_secs_f64().ceil() as u64
+            );
+            audit(None, LoginResultCode::Locked);
+            Service::login_failed(session, client_id, &msg).await?;
+            return Ok(());
+        }
+
+        let timer = self.metrics.time_find_by_credentials();
+        let found = User::find_by_credentials(
+            self.accounts.as_ref(),
+            &self.password_hasher,
+            &username,
+            &password,
+        )
+        .await;
+        timer.observe_duration();
+        match found {
             Ok(Some(user)) => {
+                self.login_limiter
+                    .record_success(&username, limiter_server)
+                    .await;
                 if user.server_login != server_id as i32 {
                     let msg = format!("Account nay thuoc may chu SV{}", user.server_login);
+                    audit(Some(user.id), LoginResultCode::WrongServer);
                     Service::login_failed(session, client_id, &msg).await?;
                     return Ok(());
                 }
-                if self.user_manager.is_online(user.id).await {
-                    Service::disconnect(session, user.id).await?;
+                if let Some(online) = self.user_manager.find(user.id).await {
+                    // Kick trên game server đang giữ user, không phải server đang gửi LOGIN
+                    self.server_registry.kick(online.server_id, user.id).await;
                     self.user_manager.remove(user.id).await;
+                    audit(Some(user.id), LoginResultCode::AlreadyOnline);
                     Service::login_failed(
                         session,
                         client_id,
@@ -64,19 +254,22 @@ impl Controller {
                 let now = Utc::now().timestamp_millis();
                 let last_logout = user.last_time_logout.timestamp_millis();
                 let seconds_pass = ((now - last_logout) / 1000) as i32;
-                let wait_login = self.config.server.second_wait_login;
+                let config = self.config.load();
+                let wait_login = config.server.second_wait_login;
 
                 if seconds_pass < wait_login {
                     let msg = format!(
                         "Vui lòng chờ {} giây để đăng nhập lại.",
                         wait_login - seconds_pass
                     );
+                    audit(Some(user.id), LoginResultCode::Cooldown);
                     Service::login_failed(session, client_id, &msg).await?;
                     return Ok(());
                 }
 
                 // Check 4: Testmode
-                if !user.is_admin && self.config.server.testmode == 1 {
+                if !user.is_admin && config.server.testmode == 1 {
+                    audit(Some(user.id), LoginResultCode::Testmode);
                     Service::login_failed(
                         session,
                         client_id,
@@ -85,7 +278,14 @@ impl Controller {
                     .await?;
                     return Ok(());
                 }
+                // Check 5: Bảo trì riêng từng game server
+                if let Some(notice) = self.maintenance.check(server_id as i32, &user).await {
+                    audit(Some(user.id), LoginResultCode::Maintenance);
+                    Service::login_failed(session, client_id, &notice).await?;
+                    return Ok(());
+                }
                 if user.ban {
+                    audit(Some(user.id), LoginResultCode::Banned);
                     Service::login_failed(
                         session,
                         client_id,
@@ -95,14 +295,19 @@ impl Controller {
                     return Ok(());
                 }
 
-                User::update_login_time(self.db.get_pool(), user.id).await?;
+                self.accounts.update_login_time(user.id).await?;
                 Service::login_successful(session, &user, client_id).await?;
+                audit(Some(user.id), LoginResultCode::Success);
                 self.user_manager
                     .add(user.id, username.clone(), server_id as i32, client_id)
                     .await;
                 println!("User {} logged in successfully", username);
             }
             Ok(None) => {
+                self.login_limiter
+                    .record_failure(&username, limiter_server)
+                    .await;
+                audit(None, LoginResultCode::WrongPassword);
                 Service::login_failed(
                     session,
                     client_id,
@@ -113,18 +318,29 @@ impl Controller {
             }
             Err(e) => {
                 eprintln!("Database error during login: {}", e);
+                audit(None, LoginResultCode::Error);
                 Service::login_failed(session, client_id, "Lỗi hệ thống, vui lòng thử lại!")
                     .await?;
             }
         }
         Ok(())
     }
-    async fn logout(&self, _session: &mut Session, mut msg: Message) -> Result<()> {
-        let user_id = msg.read_int()?;
+    async fn logout(&self, session: &Session, mut msg: Message) -> Result<()> {
+        let Logout { user_id } = Logout::decode(&mut msg)?;
         if let Some(user_info) = self.user_manager.find(user_id).await {
+            // Chỉ game server đang giữ user mới được logout user đó
+            if let Some(authenticated) = session.authenticated_server()
+                && authenticated != user_info.server_id
+            {
+                warn!(
+                    "Session {} authenticated as server {} tried to log out user {} of server {}, rejected",
+                    session.id, authenticated, user_id, user_info.server_id
+                );
+                return Ok(());
+            }
             println!("Logout user: {}", user_info.username);
 
-            if let Err(e) = User::update_logout_time(self.db.get_pool(), user_id).await {
+            if let Err(e) = self.accounts.update_logout_time(user_id).await {
                 eprintln!("Failed to update logout time: {}", e);
             }
             self.user_manager.remove(user_id).await;
@@ -132,22 +348,381 @@ impl Controller {
 
         Ok(())
     }
-    async fn set_server(&self, _session: &mut Session, mut msg: Message) -> Result<()> {
-        let server_id = msg.read_int()?;
+    async fn set_server(&self, session: &mut Session, mut msg: Message) -> Result<()> {
+        let ServerSync { server_id, users } = ServerSync::decode(&mut msg)?;
+        if let Some(authenticated) = session.authenticated_server()
+            && authenticated != server_id
+        {
+            warn!(
+                "Session {} authenticated as server {} tried to sync server {}, rejected",
+                session.id, authenticated, server_id
+            );
+            return Ok(());
+        }
+        self.link_cleanup.cancel(server_id).await;
+        session.set_server_id(server_id);
         self.user_manager.remove_all_with_server_id(server_id).await;
 
-        let size = msg.read_int()?;
-        for i in 0..size {
-            let client_id = msg.read_int()?;
-            let user_id = msg.read_int()?;
-            let username = msg.read_utf()?;
-            let _password = msg.read_utf()?;
-            println!("  [{}] Add user: {} (id: {})", i + 1, username, user_id);
+        for (i, user) in users.into_iter().enumerate() {
+            println!(
+                "  [{}] Add user: {} (id: {})",
+                i + 1,
+                user.username,
+                user.user_id
+            );
             self.user_manager
-                .add(user_id, username, server_id, client_id)
+                .add(user.user_id, user.username, server_id, user.client_id)
                 .await;
         }
+        let kicked = self
+            .server_registry
+            .register(server_id, session.handle().clone())
+            .await;
+        for user_id in kicked {
+            self.user_manager.remove(user_id).await;
+        }
         println!("Server sync completed");
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::{self, MemoryAccountRepository};
+    use crate::io::codec::MessageCodec;
+    use crate::io::packet::{LoginResponse, LoginResult};
+    use crate::model::login_history::LoginHistory;
+    use crate::model::maintenance::MaintenanceWindow;
+    use crate::model::password::is_hashed;
+    use crate::testing::{self, connect_pair};
+    use futures::StreamExt;
+    use tokio::net::TcpStream;
+    use tokio_util::codec::FramedRead;
+
+    /// Argon2 rẻ để test chạy nhanh, khóa username sau 3 lần sai
+    const EXTRA_CONFIG: &str = r#"
+[password]
+memory_kib = 1024
+iterations = 1
+parallelism = 1
+
+[login_limit]
+username_max_failures = 3
+"#;
+
+    fn config() -> crate::config::Config {
+        crate::config::Config::parse(&format!("{}{}", testing::CONFIG, EXTRA_CONFIG), []).unwrap()
+    }
+
+    fn account(id: i32, username: &str, password: &str) -> User {
+        User {
+            password: password.to_string(),
+            ..memory::account(id, username)
+        }
+    }
+
+    struct Harness {
+        controller: Controller,
+        accounts: MemoryAccountRepository,
+        user_manager: UserManager,
+        maintenance: Maintenance,
+        config: SharedConfig,
+        session: Session,
+        peer: FramedRead<TcpStream, MessageCodec>,
+    }
+
+    impl Harness {
+        async fn new(config: crate::config::Config) -> Self {
+            let (session, peer) = connect_pair(1).await;
+
+            let accounts = MemoryAccountRepository::new();
+            let user_manager = UserManager::new();
+            let maintenance = Maintenance::new();
+            let shared_config = SharedConfig::new(config.clone());
+            let controller = Controller::new(AppContext {
+                accounts: Arc::new(accounts.clone()),
+                user_manager: user_manager.clone(),
+                server_registry: ServerRegistry::new(),
+                link_cleanup: LinkCleanup::new(
+                    user_manager.clone(),
+                    Arc::new(accounts.clone()),
+                    std::time::Duration::from_secs(60),
+                ),
+                password_hasher: PasswordHasher::new(&config.password).unwrap(),
+                login_limiter: LoginLimiter::new(config.login_limit.clone()),
+                maintenance: maintenance.clone(),
+                login_history: LoginHistory::disabled(),
+                metrics: Metrics::new(),
+                config: shared_config.clone(),
+            });
+            Self {
+                controller,
+                accounts,
+                user_manager,
+                maintenance,
+                config: shared_config,
+                session,
+                peer,
+            }
+        }
+
+        async fn login(&mut self, server_id: i8, username: &str, password: &str) -> LoginResult {
+            let request = LoginRequest {
+                server_id,
+                client_id: 77,
+                username: username.to_string(),
+                password: password.to_string(),
+            };
+            self.controller
+                .process(&mut self.session, request.encode().unwrap())
+                .await
+                .unwrap();
+            let mut reply = self.peer.next().await.unwrap().unwrap();
+            let response = LoginResponse::decode(&mut reply).unwrap();
+            assert_eq!(response.client_id, 77);
+            response.result
+        }
+    }
+
+    fn failure(result: LoginResult) -> String {
+        match result {
+            LoginResult::Failed(reason) => reason,
+            LoginResult::Success(account) => panic!("unexpected success: {:?}", account),
+        }
+    }
+
+    #[tokio::test]
+    async fn successful_login_marks_user_online_and_rehashes_plaintext() {
+        let mut harness = Harness::new(config()).await;
+        harness
+            .accounts
+            .insert(account(1, "player", "123456"))
+            .await;
+        let before = Utc::now();
+
+        match harness.login(1, "player", "123456").await {
+            LoginResult::Success(account) => assert_eq!(account.user_id, 1),
+            LoginResult::Failed(reason) => panic!("login failed: {}", reason),
+        }
+        let online = harness.user_manager.find(1).await.unwrap();
+        assert_eq!((online.server_id, online.client_id), (1, 77));
+        let stored = harness.accounts.get(1).await.unwrap();
+        assert!(is_hashed(&stored.password));
+        assert!(stored.last_time_login >= before);
+    }
+
+    #[tokio::test]
+    async fn wrong_password_is_rejected_then_locked() {
+        let mut harness = Harness::new(config()).await;
+        harness
+            .accounts
+            .insert(account(1, "player", "123456"))
+            .await;
+
+        for _ in 0..3 {
+            let reason = failure(harness.login(1, "player", "sai").await);
+            assert_eq!(reason, "Thông tin tài khoản hoặc mật khẩu không chính xác");
+        }
+        let reason = failure(harness.login(1, "player", "123456").await);
+        assert!(reason.contains("sai quá nhiều lần"), "{}", reason);
+        assert!(harness.user_manager.find(1).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn account_of_another_server_is_rejected() {
+        let mut harness = Harness::new(config()).await;
+        let mut user = account(1, "player", "123456");
+        user.server_login = 2;
+        harness.accounts.insert(user).await;
+
+        let reason = failure(harness.login(1, "player", "123456").await);
+        assert_eq!(reason, "Account nay thuoc may chu SV2");
+    }
+
+    #[tokio::test]
+    async fn duplicate_login_removes_online_user() {
+        let mut harness = Harness::new(config()).await;
+        harness
+            .accounts
+            .insert(account(1, "player", "123456"))
+            .await;
+        harness
+            .user_manager
+            .add(1, "player".to_string(), 1, 5)
+            .await;
+
+        failure(harness.login(1, "player", "123456").await);
+        assert!(harness.user_manager.find(1).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn recent_logout_must_wait() {
+        let mut harness = Harness::new(config()).await;
+        let mut user = account(1, "player", "123456");
+        user.last_time_logout = Utc::now();
+        harness.accounts.insert(user).await;
+
+        let reason = failure(harness.login(1, "player", "123456").await);
+        assert!(reason.starts_with("Vui lòng chờ"), "{}", reason);
+    }
+
+    #[tokio::test]
+    async fn testmode_only_lets_admins_in() {
+        let mut config = config();
+        config.server.testmode = 1;
+        let mut harness = Harness::new(config).await;
+        harness
+            .accounts
+            .insert(account(1, "player", "123456"))
+            .await;
+        let mut admin = account(2, "admin", "123456");
+        admin.is_admin = true;
+        harness.accounts.insert(admin).await;
+
+        failure(harness.login(1, "player", "123456").await);
+        assert!(matches!(
+            harness.login(1, "admin", "123456").await,
+            LoginResult::Success(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn reloaded_config_applies_to_running_session() {
+        let mut harness = Harness::new(config()).await;
+        harness
+            .accounts
+            .insert(account(1, "player", "123456"))
+            .await;
+        let mut reloaded = config();
+        reloaded.server.testmode = 1;
+        harness.config.store(reloaded);
+
+        failure(harness.login(1, "player", "123456").await);
+        let mut reloaded = config();
+        reloaded.server.testmode = 0;
+        harness.config.store(reloaded);
+        assert!(matches!(
+            harness.login(1, "player", "123456").await,
+            LoginResult::Success(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn banned_account_is_rejected() {
+        let mut harness = Harness::new(config()).await;
+        let mut user = account(1, "player", "123456");
+        user.ban = true;
+        harness.accounts.insert(user).await;
+
+        let reason = failure(harness.login(1, "player", "123456").await);
+        assert_eq!(reason, "Tài khoản đã bị khóa do vi phạm điều khoản!");
+        assert!(harness.user_manager.find(1).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn logout_updates_time_and_removes_user() {
+        let mut harness = Harness::new(config()).await;
+        harness
+            .accounts
+            .insert(account(1, "player", "123456"))
+            .await;
+        harness.login(1, "player", "123456").await;
+        let before = Utc::now();
+
+        harness
+            .controller
+            .process(
+                &mut harness.session,
+                Logout { user_id: 1 }.encode().unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(harness.user_manager.find(1).await.is_none());
+        assert!(harness.accounts.get(1).await.unwrap().last_time_logout >= before);
+    }
+
+    #[tokio::test]
+    async fn logout_of_another_servers_user_is_rejected() {
+        let mut config = config();
+        config.auth.enabled = true;
+        let mut harness = Harness::new(config).await;
+        harness.session.set_authenticated_server(1);
+        let mut user = account(2, "player", "123456");
+        user.server_login = 2;
+        harness.accounts.insert(user).await;
+        harness
+            .user_manager
+            .add(2, "player".to_string(), 2, 5)
+            .await;
+        let before = harness.accounts.get(2).await.unwrap().last_time_logout;
+
+        harness
+            .controller
+            .process(
+                &mut harness.session,
+                Logout { user_id: 2 }.encode().unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(harness.user_manager.find(2).await.unwrap().server_id, 2);
+        assert_eq!(
+            harness.accounts.get(2).await.unwrap().last_time_logout,
+            before
+        );
+    }
+
+    #[tokio::test]
+    async fn ping_is_answered_before_authentication() {
+        let mut config = config();
+        config.auth.enabled = true;
+        let mut harness = Harness::new(config).await;
+
+        harness
+            .controller
+            .process(&mut harness.session, Ping { nonce: 7 }.encode().unwrap())
+            .await
+            .unwrap();
+        let mut reply = harness.peer.next().await.unwrap().unwrap();
+        assert_eq!(reply.command, command::PONG);
+        assert_eq!(Pong::decode(&mut reply).unwrap().nonce, 7);
+    }
+
+    #[tokio::test]
+    async fn maintenance_blocks_only_its_server_except_whitelist() {
+        let mut harness = Harness::new(config()).await;
+        let mut other = account(1, "player", "123456");
+        other.server_login = 2;
+        harness.accounts.insert(other).await;
+        harness
+            .accounts
+            .insert(account(2, "tester", "123456"))
+            .await;
+        harness
+            .accounts
+            .insert(account(3, "player3", "123456"))
+            .await;
+        harness
+            .maintenance
+            .set(
+                1,
+                MaintenanceWindow {
+                    message: "Bảo trì server 1".to_string(),
+                    whitelist: vec!["tester".to_string()],
+                    ..MaintenanceWindow::default()
+                },
+            )
+            .await;
+
+        let reason = failure(harness.login(1, "player3", "123456").await);
+        assert_eq!(reason, "Bảo trì server 1");
+        assert!(matches!(
+            harness.login(1, "tester", "123456").await,
+            LoginResult::Success(_)
+        ));
+        assert!(matches!(
+            harness.login(2, "player", "123456").await,
+            LoginResult::Success(_)
+        ));
+    }
+}
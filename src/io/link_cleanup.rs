@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::db::account::AccountRepository;
+use crate::model::user_manager::UserManager;
+
+/// Dọn user của game server bị mất kết nối (crash, không gửi LOGOUT) sau một khoảng chờ.
+/// Game server kết nối lại và gửi SET_SERVER trong khoảng chờ thì hủy việc dọn.
+#[derive(Clone)]
+pub struct LinkCleanup {
+    pending: Arc<Mutex<HashMap<i32, CancellationToken>>>,
+    user_manager: UserManager,
+    accounts: Arc<dyn AccountRepository>,
+    grace: Duration,
+}
+
+impl LinkCleanup {
+    pub fn new(
+        user_manager: UserManager,
+        accounts: Arc<dyn AccountRepository>,
+        grace: Duration,
+    ) -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            user_manager,
+            accounts,
+            grace,
+        }
+    }
+
+    /// Hẹn dọn user của `server_id` sau `grace`, thay thế lịch cũ nếu có
+    pub async fn schedule(&self, server_id: i32) {
+        let token = CancellationToken::new();
+        if let Some(old) = self.pending.lock().await.insert(server_id, token.clone()) {
+            old.cancel();
+        }
+        info!(
+            "Server {} link lost, clearing its users in {}s unless it reconnects",
+            server_id,
+            self.grace.as_secs()
+        );
+        let cleanup = self.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = token.cancelled() => {}
+                _ = tokio::time::sleep(cleanup.grace) => cleanup.run(server_id, &token).await,
+            }
+        });
+    }
+
+    /// Hủy lịch dọn của `server_id` (game server đã gửi lại SET_SERVER).
+    /// Nếu đang dọn thì chờ dọn xong rồi mới trả về.
+    pub async fn cancel(&self, server_id: i32) -> bool {
+        let mut pending = self.pending.lock().await;
+        match pending.remove(&server_id) {
+            Some(token) => {
+                token.cancel();
+                info!("Server {} reconnected, cleanup cancelled", server_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Giữ lock trong suốt quá trình dọn để SET_SERVER không chen vào giữa
+    async fn run(&self, server_id: i32, token: &CancellationToken) {
+        let mut pending = self.pending.lock().await;
+        if token.is_cancelled() {
+            return;
+        }
+        pending.remove(&server_id);
+
+        let mut cleared = 0;
+        for user in self.user_manager.all().await {
+            if user.server_id != server_id {
+                continue;
+            }
+            if let Err(e) = self.accounts.update_logout_time(user.user_id).await {
+                warn!(
+                    "Cannot update logout time of {} ({}): {}",
+                    user.username, user.user_id, e
+                );
+            }
+            self.user_manager.remove(user.user_id).await;
+            cleared += 1;
+        }
+        info!(
+            "Server {} did not reconnect, cleared {} online users",
+            server_id, cleared
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::{MemoryAccountRepository, account};
+    use chrono::{TimeDelta, Utc};
+
+    async fn setup() -> (LinkCleanup, MemoryAccountRepository, UserManager) {
+        let accounts = MemoryAccountRepository::new();
+        for id in 1..=3 {
+            accounts.insert(account(id, &format!("user{}", id))).await;
+        }
+        let user_manager = UserManager::new();
+        user_manager.add(1, "user1".to_string(), 1, 10).await;
+        user_manager.add(2, "user2".to_string(), 1, 11).await;
+        user_manager.add(3, "user3".to_string(), 2, 12).await;
+        let cleanup = LinkCleanup::new(
+            user_manager.clone(),
+            Arc::new(accounts.clone()),
+            Duration::from_secs(30),
+        );
+        (cleanup, accounts, user_manager)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn users_are_cleared_after_grace_period() {
+        let (cleanup, accounts, user_manager) = setup().await;
+        cleanup.schedule(1).await;
+
+        tokio::time::sleep(Duration::from_secs(29)).await;
+        assert!(user_manager.find(1).await.is_some());
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(user_manager.find(1).await.is_none());
+        assert!(user_manager.find(2).await.is_none());
+        assert!(user_manager.find(3).await.is_some());
+        let an_hour_ago = Utc::now() - TimeDelta::hours(1);
+        assert!(accounts.get(1).await.unwrap().last_time_logout > an_hour_ago);
+        assert!(accounts.get(3).await.unwrap().last_time_logout < an_hour_ago);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reconnect_within_grace_cancels_cleanup() {
+        let (cleanup, _accounts, user_manager) = setup().await;
+        cleanup.schedule(1).await;
+        tokio::time::sleep(Duration::from_secs(10)).await;
+
+        assert!(cleanup.cancel(1).await);
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert!(user_manager.find(1).await.is_some());
+        assert!(!cleanup.cancel(1).await);
+    }
+}
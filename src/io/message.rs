@@ -1,8 +1,47 @@
-use anyhow::Result;
+use super::mutf8;
+use crate::config::StringEncoding;
 use bytes::{Buf, BufMut, BytesMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+
+/// `true` khi dùng UTF-8 chuẩn thay cho modified UTF-8 của Java
+static PLAIN_UTF8: AtomicBool = AtomicBool::new(false);
+
+/// Chọn cách mã hóa chuỗi cho toàn bộ `Message` (đọc từ `ServerConfig::string_encoding`)
+pub fn set_string_encoding(encoding: StringEncoding) {
+    PLAIN_UTF8.store(encoding == StringEncoding::Utf8, Ordering::Relaxed);
+}
+
+pub fn string_encoding() -> StringEncoding {
+    if PLAIN_UTF8.load(Ordering::Relaxed) {
+        StringEncoding::Utf8
+    } else {
+        StringEncoding::ModifiedUtf8
+    }
+}
+
+/// Lỗi decode gói tin từ game server (frame bị cắt ngắn hoặc sai định dạng)
+#[derive(Debug, Error)]
+pub enum ProtocolError {
+    #[error(
+        "truncated {field} at offset {offset}: expected {expected} bytes, {available} available"
+    )]
+    Truncated {
+        field: &'static str,
+        offset: usize,
+        expected: usize,
+        available: usize,
+    },
+    #[error("invalid utf-8 in {field} at offset {offset}")]
+    InvalidUtf8 { field: &'static str, offset: usize },
+    #[error("string too long: {len} bytes encoded, max {}", u16::MAX)]
+    StringTooLong { len: usize },
+}
+
 pub struct Message {
     pub command: i8,
     data: BytesMut,
+    offset: usize,
 }
 
 impl Message {
@@ -10,12 +49,14 @@ impl Message {
         Self {
             command,
             data: BytesMut::new(),
+            offset: 0,
         }
     }
     pub fn with_data(command: i8, data: Vec<u8>) -> Self {
         Self {
             command,
             data: BytesMut::from(&data[..]),
+            offset: 0,
         }
     }
     pub fn write_byte(&mut self, value: i8) {
@@ -30,29 +71,175 @@ impl Message {
     pub fn write_bool(&mut self, value: bool) {
         self.data.put_u8(if value { 1 } else { 0 });
     }
-    pub fn write_utf(&mut self, value: &str) {
-        let bytes = value.as_bytes();
-        self.data.put_u16(bytes.len() as u16);
+    pub fn write_utf(&mut self, value: &str) -> Result<(), ProtocolError> {
+        self.write_utf_with(value, string_encoding())
+    }
+    pub fn write_utf_with(
+        &mut self,
+        value: &str,
+        encoding: StringEncoding,
+    ) -> Result<(), ProtocolError> {
+        let encoded;
+        let bytes = match encoding {
+            StringEncoding::ModifiedUtf8 => {
+                encoded = mutf8::encode(value);
+                &encoded[..]
+            }
+            StringEncoding::Utf8 => value.as_bytes(),
+        };
+        let len = u16::try_from(bytes.len())
+            .map_err(|_| ProtocolError::StringTooLong { len: bytes.len() })?;
+        self.data.put_u16(len);
         self.data.put_slice(bytes);
+        Ok(())
+    }
+    pub fn write_bytes(&mut self, value: &[u8]) -> Result<(), ProtocolError> {
+        let len = u16::try_from(value.len())
+            .map_err(|_| ProtocolError::StringTooLong { len: value.len() })?;
+        self.data.put_u16(len);
+        self.data.put_slice(value);
+        Ok(())
+    }
+
+    /// Kiểm tra còn đủ `expected` bytes để đọc, nếu không trả về `ProtocolError::Truncated`
+    fn ensure(&self, field: &'static str, expected: usize) -> Result<(), ProtocolError> {
+        let available = self.data.remaining();
+        if available < expected {
+            return Err(ProtocolError::Truncated {
+                field,
+                offset: self.offset,
+                expected,
+                available,
+            });
+        }
+        Ok(())
     }
-    pub fn read_byte(&mut self) -> Result<i8> {
+    pub fn read_byte(&mut self) -> Result<i8, ProtocolError> {
+        self.ensure("byte", 1)?;
+        self.offset += 1;
         Ok(self.data.get_i8())
     }
-    pub fn read_int(&mut self) -> Result<i32> {
+    pub fn read_int(&mut self) -> Result<i32, ProtocolError> {
+        self.ensure("int", 4)?;
+        self.offset += 4;
         Ok(self.data.get_i32())
     }
-    pub fn read_long(&mut self) -> Result<i64> {
+    pub fn read_long(&mut self) -> Result<i64, ProtocolError> {
+        self.ensure("long", 8)?;
+        self.offset += 8;
         Ok(self.data.get_i64())
     }
-    pub fn read_bool(&mut self) -> Result<bool> {
+    pub fn read_bool(&mut self) -> Result<bool, ProtocolError> {
+        self.ensure("bool", 1)?;
+        self.offset += 1;
         Ok(self.data.get_u8() != 0)
     }
-    pub fn read_utf(&mut self) -> Result<String> {
+    pub fn read_utf(&mut self) -> Result<String, ProtocolError> {
+        self.read_utf_with(string_encoding())
+    }
+    pub fn read_utf_with(&mut self, encoding: StringEncoding) -> Result<String, ProtocolError> {
+        self.ensure("utf length", 2)?;
         let len = self.data.get_u16() as usize;
+        self.offset += 2;
+        self.ensure("utf", len)?;
+        let start = self.offset;
         let bytes = self.data.split_to(len);
-        Ok(String::from_utf8(bytes.to_vec())?)
+        self.offset += len;
+        let decoded = match encoding {
+            StringEncoding::ModifiedUtf8 => mutf8::decode(&bytes),
+            StringEncoding::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+        };
+        decoded.ok_or(ProtocolError::InvalidUtf8 {
+            field: "utf",
+            offset: start,
+        })
+    }
+    pub fn read_bytes(&mut self) -> Result<Vec<u8>, ProtocolError> {
+        self.ensure("bytes length", 2)?;
+        let len = self.data.get_u16() as usize;
+        self.offset += 2;
+        self.ensure("bytes", len)?;
+        self.offset += len;
+        Ok(self.data.split_to(len).to_vec())
     }
     pub fn get_data(&self) -> &[u8] {
         &self.data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_past_end_returns_truncated() {
+        let mut msg = Message::with_data(1, vec![0, 0, 0, 7, 1]);
+        assert_eq!(msg.read_int().unwrap(), 7);
+        assert!(msg.read_bool().unwrap());
+
+        match msg.read_int() {
+            Err(ProtocolError::Truncated {
+                field,
+                offset,
+                expected,
+                available,
+            }) => {
+                assert_eq!(field, "int");
+                assert_eq!(offset, 5);
+                assert_eq!(expected, 4);
+                assert_eq!(available, 0);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn utf_length_larger_than_frame() {
+        let mut msg = Message::with_data(1, vec![0, 10, b'a', b'b']);
+        match msg.read_utf() {
+            Err(ProtocolError::Truncated {
+                field,
+                offset,
+                expected,
+                available,
+            }) => {
+                assert_eq!(field, "utf");
+                assert_eq!(offset, 2);
+                assert_eq!(expected, 10);
+                assert_eq!(available, 2);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_reported() {
+        let mut msg = Message::with_data(1, vec![0, 2, 0xC3, 0x28]);
+        assert!(matches!(
+            msg.read_utf(),
+            Err(ProtocolError::InvalidUtf8 { offset: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn utf_round_trip_in_both_encodings() {
+        let text = "Tài khoản\0😀";
+        for encoding in [StringEncoding::ModifiedUtf8, StringEncoding::Utf8] {
+            let mut msg = Message::new(1);
+            msg.write_utf_with(text, encoding).unwrap();
+            let mut msg = Message::with_data(1, msg.get_data().to_vec());
+            assert_eq!(msg.read_utf_with(encoding).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn oversize_string_is_rejected() {
+        let mut msg = Message::new(1);
+        let text = "a".repeat(u16::MAX as usize + 1);
+        assert!(matches!(
+            msg.write_utf(&text),
+            Err(ProtocolError::StringTooLong { len: 65536 })
+        ));
+        assert!(msg.get_data().is_empty());
+    }
+}
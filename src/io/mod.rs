@@ -1,4 +1,11 @@
+pub mod auth;
+pub mod codec;
 pub mod controller;
+pub mod link_cleanup;
 pub mod message;
+pub mod mutf8;
+pub mod packet;
+pub mod server_registry;
 pub mod service;
 pub mod session;
+pub mod tls;
@@ -0,0 +1,103 @@
+//! Modified UTF-8 giống `DataOutputStream.writeUTF` / `DataInputStream.readUTF` của Java:
+//! ký tự NUL được mã hóa thành 2 byte (`C0 80`) và ký tự ngoài BMP (emoji...) được
+//! tách thành cặp surrogate, mỗi surrogate 3 byte.
+
+/// Mã hóa chuỗi sang modified UTF-8 (chưa bao gồm 2 byte độ dài)
+pub fn encode(value: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0001..=0x007F => out.push(unit as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                out.push(0xC0 | (unit >> 6) as u8);
+                out.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                out.push(0xE0 | (unit >> 12) as u8);
+                out.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                out.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+    out
+}
+
+/// Giải mã modified UTF-8, trả về `None` nếu byte sai định dạng hoặc có surrogate lẻ
+pub fn decode(bytes: &[u8]) -> Option<String> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b >> 4 {
+            0x0..=0x7 => {
+                units.push(b as u16);
+                i += 1;
+            }
+            0xC | 0xD => {
+                let b2 = *bytes.get(i + 1)?;
+                if b2 & 0xC0 != 0x80 {
+                    return None;
+                }
+                units.push(((b as u16 & 0x1F) << 6) | (b2 as u16 & 0x3F));
+                i += 2;
+            }
+            0xE => {
+                let b2 = *bytes.get(i + 1)?;
+                let b3 = *bytes.get(i + 2)?;
+                if b2 & 0xC0 != 0x80 || b3 & 0xC0 != 0x80 {
+                    return None;
+                }
+                units.push(
+                    ((b as u16 & 0x0F) << 12) | ((b2 as u16 & 0x3F) << 6) | (b3 as u16 & 0x3F),
+                );
+                i += 3;
+            }
+            _ => return None,
+        }
+    }
+    String::from_utf16(&units).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_is_unchanged() {
+        assert_eq!(encode("admin123"), b"admin123");
+        assert_eq!(decode(b"admin123").unwrap(), "admin123");
+    }
+
+    #[test]
+    fn vietnamese_diacritics_round_trip() {
+        let text = "Đăng nhập thất bại, vui lòng đăng nhập lại!";
+        // Ký tự BMP mã hóa giống UTF-8 chuẩn
+        assert_eq!(encode(text), text.as_bytes());
+        assert_eq!(decode(&encode(text)).unwrap(), text);
+    }
+
+    #[test]
+    fn nul_uses_two_bytes() {
+        assert_eq!(encode("a\0b"), vec![b'a', 0xC0, 0x80, b'b']);
+        assert_eq!(decode(&[b'a', 0xC0, 0x80, b'b']).unwrap(), "a\0b");
+    }
+
+    #[test]
+    fn emoji_is_encoded_as_surrogate_pair() {
+        // U+1F600 -> D83D DE00
+        let encoded = encode("😀");
+        assert_eq!(encoded, vec![0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]);
+        assert_eq!(decode(&encoded).unwrap(), "😀");
+    }
+
+    #[test]
+    fn rejects_standard_four_byte_sequence() {
+        assert!(decode("😀".as_bytes()).is_none());
+    }
+
+    #[test]
+    fn rejects_lone_surrogate_and_truncated_input() {
+        assert!(decode(&[0xED, 0xA0, 0xBD]).is_none());
+        assert!(decode(&[0xC3]).is_none());
+    }
+}
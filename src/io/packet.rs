@@ -0,0 +1,533 @@
+use super::message::{Message, ProtocolError};
+use crate::command;
+
+/// Định nghĩa layout của một gói tin, dùng chung cho login server và các tool
+pub trait Packet: Sized {
+    const COMMAND: i8;
+
+    /// Ghi phần data của gói tin vào `msg`
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError>;
+    /// Đọc gói tin từ phần data của `msg`
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError>;
+
+    fn encode(&self) -> Result<Message, ProtocolError> {
+        let mut msg = Message::new(Self::COMMAND);
+        self.write(&mut msg)?;
+        Ok(msg)
+    }
+}
+
+/// Game server -> login server: yêu cầu đăng nhập
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginRequest {
+    pub server_id: i8,
+    pub client_id: i32,
+    pub username: String,
+    pub password: String,
+}
+
+impl LoginRequest {
+    /// Đọc `client_id` mà không tiêu thụ message, để còn trả lời khi phần sau bị hỏng
+    pub fn peek_client_id(msg: &Message) -> Option<i32> {
+        let bytes = msg.get_data().get(1..5)?;
+        Some(i32::from_be_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl Packet for LoginRequest {
+    const COMMAND: i8 = command::LOGIN;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_byte(self.server_id);
+        msg.write_int(self.client_id);
+        msg.write_utf(&self.username)?;
+        msg.write_utf(&self.password)?;
+        Ok(())
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            server_id: msg.read_byte()?,
+            client_id: msg.read_int()?,
+            username: msg.read_utf()?,
+            password: msg.read_utf()?,
+        })
+    }
+}
+
+/// Login server -> game server: kết quả đăng nhập
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginResponse {
+    pub client_id: i32,
+    pub result: LoginResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginResult {
+    Success(LoginAccount),
+    Failed(String),
+}
+
+/// Thông tin tài khoản gửi kèm khi đăng nhập thành công
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginAccount {
+    pub user_id: i32,
+    pub is_admin: bool,
+    pub active: bool,
+    pub thoi_vang: i32,
+    pub last_time_login: i64,
+    pub last_time_logout: i64,
+    pub reward: String,
+    pub server_login: i32,
+    pub tongnap: i32,
+    pub vnd: i32,
+}
+
+impl LoginResponse {
+    pub fn success(client_id: i32, account: LoginAccount) -> Self {
+        Self {
+            client_id,
+            result: LoginResult::Success(account),
+        }
+    }
+    pub fn failed(client_id: i32, reason: &str) -> Self {
+        Self {
+            client_id,
+            result: LoginResult::Failed(reason.to_string()),
+        }
+    }
+}
+
+impl Packet for LoginResponse {
+    const COMMAND: i8 = command::LOGIN;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_int(self.client_id);
+        match &self.result {
+            LoginResult::Success(account) => {
+                msg.write_byte(0);
+                msg.write_int(account.user_id);
+                msg.write_bool(account.is_admin);
+                msg.write_bool(account.active);
+                msg.write_int(account.thoi_vang);
+                msg.write_long(account.last_time_login);
+                msg.write_long(account.last_time_logout);
+                msg.write_utf(&account.reward)?;
+                msg.write_int(0); // ruby - not used
+                msg.write_int(0); // moc_nap - not used
+                msg.write_int(account.server_login);
+                msg.write_int(0); // is_use_ma_bao_ve - not used
+                msg.write_int(0); // ma_bao_ve - not used
+                msg.write_int(account.tongnap);
+                msg.write_int(account.vnd);
+            }
+            LoginResult::Failed(reason) => {
+                msg.write_byte(1);
+                msg.write_utf(reason)?;
+            }
+        }
+        Ok(())
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        let client_id = msg.read_int()?;
+        let result = if msg.read_byte()? == 0 {
+            let user_id = msg.read_int()?;
+            let is_admin = msg.read_bool()?;
+            let active = msg.read_bool()?;
+            let thoi_vang = msg.read_int()?;
+            let last_time_login = msg.read_long()?;
+            let last_time_logout = msg.read_long()?;
+            let reward = msg.read_utf()?;
+            msg.read_int()?; // ruby
+            msg.read_int()?; // moc_nap
+            let server_login = msg.read_int()?;
+            msg.read_int()?; // is_use_ma_bao_ve
+            msg.read_int()?; // ma_bao_ve
+            let tongnap = msg.read_int()?;
+            let vnd = msg.read_int()?;
+            LoginResult::Success(LoginAccount {
+                user_id,
+                is_admin,
+                active,
+                thoi_vang,
+                last_time_login,
+                last_time_logout,
+                reward,
+                server_login,
+                tongnap,
+                vnd,
+            })
+        } else {
+            LoginResult::Failed(msg.read_utf()?)
+        };
+        Ok(Self { client_id, result })
+    }
+}
+
+/// Game server -> login server: user thoát game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Logout {
+    pub user_id: i32,
+}
+
+impl Packet for Logout {
+    const COMMAND: i8 = command::LOGOUT;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_int(self.user_id);
+        Ok(())
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            user_id: msg.read_int()?,
+        })
+    }
+}
+
+/// Login server -> game server: kick user đang online
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disconnect {
+    pub user_id: i32,
+}
+
+impl Packet for Disconnect {
+    const COMMAND: i8 = command::DISCONNECT;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_int(self.user_id);
+        Ok(())
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            user_id: msg.read_int()?,
+        })
+    }
+}
+
+/// Login server -> game server: thông báo gửi tới client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerMessage {
+    pub client_id: i32,
+    pub text: String,
+}
+
+impl Packet for ServerMessage {
+    const COMMAND: i8 = command::SERVER_MESSAGE;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_int(self.client_id);
+        msg.write_utf(&self.text)?;
+        Ok(())
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            client_id: msg.read_int()?,
+            text: msg.read_utf()?,
+        })
+    }
+}
+
+/// Game server -> login server: đồng bộ danh sách user đang online
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerSync {
+    pub server_id: i32,
+    pub users: Vec<SyncedUser>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncedUser {
+    pub client_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub password: String,
+}
+
+impl Packet for ServerSync {
+    const COMMAND: i8 = command::SET_SERVER;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_int(self.server_id);
+        msg.write_int(self.users.len() as i32);
+        for user in &self.users {
+            msg.write_int(user.client_id);
+            msg.write_int(user.user_id);
+            msg.write_utf(&user.username)?;
+            msg.write_utf(&user.password)?;
+        }
+        Ok(())
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        let server_id = msg.read_int()?;
+        let size = msg.read_int()?;
+        let mut users = Vec::new();
+        for _ in 0..size {
+            users.push(SyncedUser {
+                client_id: msg.read_int()?,
+                user_id: msg.read_int()?,
+                username: msg.read_utf()?,
+                password: msg.read_utf()?,
+            });
+        }
+        Ok(Self { server_id, users })
+    }
+}
+
+/// Login server -> game server: yêu cầu cập nhật thời gian logout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateTimeLogout {
+    pub user_id: i32,
+}
+
+impl Packet for UpdateTimeLogout {
+    const COMMAND: i8 = command::UPDATE_TIME_LOGOUT;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_int(self.user_id);
+        Ok(())
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            user_id: msg.read_int()?,
+        })
+    }
+}
+
+/// Login server -> game server: nonce để game server ký
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthChallenge {
+    pub nonce: Vec<u8>,
+}
+
+impl Packet for AuthChallenge {
+    const COMMAND: i8 = command::AUTH_CHALLENGE;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_bytes(&self.nonce)
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            nonce: msg.read_bytes()?,
+        })
+    }
+}
+
+/// Game server -> login server: HMAC của nonce theo secret của server_id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthResponse {
+    pub server_id: i32,
+    pub mac: Vec<u8>,
+}
+
+impl Packet for AuthResponse {
+    const COMMAND: i8 = command::AUTH_RESPONSE;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_int(self.server_id);
+        msg.write_bytes(&self.mac)
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            server_id: msg.read_int()?,
+            mac: msg.read_bytes()?,
+        })
+    }
+}
+
+/// Login server -> game server: kết quả xác thực
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthResult {
+    pub accepted: bool,
+    pub reason: String,
+}
+
+impl Packet for AuthResult {
+    const COMMAND: i8 = command::AUTH_RESULT;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_bool(self.accepted);
+        msg.write_utf(&self.reason)
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            accepted: msg.read_bool()?,
+            reason: msg.read_utf()?,
+        })
+    }
+}
+
+/// Heartbeat hai chiều, bên nhận trả lại PONG với cùng `nonce`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ping {
+    pub nonce: i64,
+}
+
+impl Packet for Ping {
+    const COMMAND: i8 = command::PING;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_long(self.nonce);
+        Ok(())
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            nonce: msg.read_long()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pong {
+    pub nonce: i64,
+}
+
+impl Packet for Pong {
+    const COMMAND: i8 = command::PONG;
+
+    fn write(&self, msg: &mut Message) -> Result<(), ProtocolError> {
+        msg.write_long(self.nonce);
+        Ok(())
+    }
+    fn decode(msg: &mut Message) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            nonce: msg.read_long()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Debug;
+
+    fn round_trip<P: Packet + PartialEq + Debug>(packet: P) {
+        let encoded = packet.encode().unwrap();
+        assert_eq!(encoded.command, P::COMMAND);
+        let mut msg = Message::with_data(encoded.command, encoded.get_data().to_vec());
+        assert_eq!(P::decode(&mut msg).unwrap(), packet);
+        assert!(msg.get_data().is_empty(), "trailing bytes after decode");
+    }
+
+    #[test]
+    fn login_request_round_trip() {
+        round_trip(LoginRequest {
+            server_id: 2,
+            client_id: 1234,
+            username: "ahwuoc".to_string(),
+            password: "mật khẩu".to_string(),
+        });
+    }
+
+    #[test]
+    fn login_request_peek_client_id() {
+        let packet = LoginRequest {
+            server_id: 1,
+            client_id: 0x01020304,
+            username: String::new(),
+            password: String::new(),
+        };
+        let msg = packet.encode().unwrap();
+        assert_eq!(LoginRequest::peek_client_id(&msg), Some(0x01020304));
+        assert_eq!(LoginRequest::peek_client_id(&Message::new(1)), None);
+    }
+
+    #[test]
+    fn login_response_success_round_trip() {
+        round_trip(LoginResponse::success(
+            7,
+            LoginAccount {
+                user_id: 42,
+                is_admin: true,
+                active: false,
+                thoi_vang: 100,
+                last_time_login: 1_700_000_000_000,
+                last_time_logout: 1_700_000_100_000,
+                reward: "1,2,3".to_string(),
+                server_login: 1,
+                tongnap: 50_000,
+                vnd: 20_000,
+            },
+        ));
+    }
+
+    #[test]
+    fn login_response_failed_round_trip() {
+        round_trip(LoginResponse::failed(
+            7,
+            "Tài khoản đã bị khóa do vi phạm điều khoản!",
+        ));
+    }
+
+    #[test]
+    fn login_response_failed_layout() {
+        let msg = LoginResponse::failed(1, "x").encode().unwrap();
+        assert_eq!(msg.get_data(), &[0, 0, 0, 1, 1, 0, 1, b'x']);
+    }
+
+    #[test]
+    fn logout_round_trip() {
+        round_trip(Logout { user_id: 99 });
+    }
+
+    #[test]
+    fn ping_pong_round_trip() {
+        round_trip(Ping { nonce: 42 });
+        round_trip(Pong { nonce: -1 });
+    }
+
+    #[test]
+    fn disconnect_round_trip() {
+        round_trip(Disconnect { user_id: 99 });
+    }
+
+    #[test]
+    fn server_message_round_trip() {
+        round_trip(ServerMessage {
+            client_id: -1,
+            text: "Bảo trì lúc 22h".to_string(),
+        });
+    }
+
+    #[test]
+    fn server_sync_round_trip() {
+        round_trip(ServerSync {
+            server_id: 3,
+            users: vec![
+                SyncedUser {
+                    client_id: 1,
+                    user_id: 10,
+                    username: "a".to_string(),
+                    password: "b".to_string(),
+                },
+                SyncedUser {
+                    client_id: 2,
+                    user_id: 11,
+                    username: "c".to_string(),
+                    password: "d".to_string(),
+                },
+            ],
+        });
+        round_trip(ServerSync {
+            server_id: 4,
+            users: vec![],
+        });
+    }
+
+    #[test]
+    fn update_time_logout_round_trip() {
+        round_trip(UpdateTimeLogout { user_id: 5 });
+    }
+
+    #[test]
+    fn auth_packets_round_trip() {
+        round_trip(AuthChallenge {
+            nonce: vec![1, 2, 3, 255],
+        });
+        round_trip(AuthResponse {
+            server_id: 1,
+            mac: vec![9; 32],
+        });
+        round_trip(AuthResult {
+            accepted: false,
+            reason: "Sai chữ ký".to_string(),
+        });
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use super::packet::Disconnect;
+use super::session::SessionHandle;
+
+/// Danh sách game server đang kết nối, theo server_id (đăng ký khi nhận SET_SERVER)
+#[derive(Clone, Default)]
+pub struct ServerRegistry {
+    servers: Arc<RwLock<HashMap<i32, SessionHandle>>>,
+    /// User cần kick nhưng game server sở hữu chưa kết nối, gửi lại khi server đó đăng ký
+    pending_kicks: Arc<RwLock<HashMap<i32, Vec<i32>>>>,
+}
+
+impl ServerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Đăng ký session cho server_id và gửi các lệnh kick đang chờ.
+    /// Trả về danh sách user_id đã được kick.
+    pub async fn register(&self, server_id: i32, handle: SessionHandle) -> Vec<i32> {
+        if let Some(old) = self.servers.write().await.insert(server_id, handle.clone())
+            && old.id() != handle.id()
+        {
+            warn!(
+                "Server {} re-registered by session {} (was session {})",
+                server_id,
+                handle.id(),
+                old.id()
+            );
+        }
+
+        let pending = self
+            .pending_kicks
+            .write()
+            .await
+            .remove(&server_id)
+            .unwrap_or_default();
+        let mut kicked = Vec::with_capacity(pending.len());
+        for user_id in pending {
+            if let Err(e) = handle.send_packet(&Disconnect { user_id }).await {
+                warn!("Failed to deliver pending kick of user {}: {}", user_id, e);
+                self.add_pending(server_id, user_id).await;
+                continue;
+            }
+            info!(
+                "Delivered pending kick of user {} to server {}",
+                user_id, server_id
+            );
+            kicked.push(user_id);
+        }
+        kicked
+    }
+
+    /// Xóa đăng ký khi session đóng, chỉ khi server_id vẫn trỏ tới đúng session đó.
+    /// Trả về `true` nếu đã xóa.
+    pub async fn unregister(&self, server_id: i32, session_id: i32) -> bool {
+        let mut servers = self.servers.write().await;
+        if servers
+            .get(&server_id)
+            .is_some_and(|handle| handle.id() == session_id)
+        {
+            servers.remove(&server_id);
+            return true;
+        }
+        false
+    }
+
+    /// Tìm session đang kết nối của server_id
+    pub async fn get(&self, server_id: i32) -> Option<SessionHandle> {
+        let servers = self.servers.read().await;
+        servers
+            .get(&server_id)
+            .filter(|handle| handle.is_connected())
+            .cloned()
+    }
+
+    /// Mọi game server đang kết nối
+    pub async fn all(&self) -> Vec<(i32, SessionHandle)> {
+        let servers = self.servers.read().await;
+        servers
+            .iter()
+            .filter(|(_, handle)| handle.is_connected())
+            .map(|(server_id, handle)| (*server_id, handle.clone()))
+            .collect()
+    }
+
+    /// Gửi DISCONNECT tới game server đang giữ user.
+    /// Nếu server đó chưa kết nối thì lưu lại để gửi khi server đăng ký, trả về `false`.
+    pub async fn kick(&self, server_id: i32, user_id: i32) -> bool {
+        if let Some(handle) = self.get(server_id).await {
+            match handle.send_packet(&Disconnect { user_id }).await {
+                Ok(()) => return true,
+                Err(e) => warn!(
+                    "Failed to send kick of user {} to server {}: {}",
+                    user_id, server_id, e
+                ),
+            }
+        } else {
+            warn!(
+                "Server {} is not connected, kick of user {} is pending",
+                server_id, user_id
+            );
+        }
+        self.add_pending(server_id, user_id).await;
+        false
+    }
+
+    /// Danh sách user đang chờ kick của server_id
+    pub async fn pending_kicks(&self, server_id: i32) -> Vec<i32> {
+        let pending = self.pending_kicks.read().await;
+        pending.get(&server_id).cloned().unwrap_or_default()
+    }
+
+    async fn add_pending(&self, server_id: i32, user_id: i32) {
+        let mut pending = self.pending_kicks.write().await;
+        let users = pending.entry(server_id).or_default();
+        if !users.contains(&user_id) {
+            users.push(user_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command;
+    use crate::testing::connect_pair;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn kick_goes_to_owning_server() {
+        let registry = ServerRegistry::new();
+        let (server1, mut peer1) = connect_pair(1).await;
+        let (server2, _peer2) = connect_pair(2).await;
+        registry.register(1, server1.handle().clone()).await;
+        registry.register(2, server2.handle().clone()).await;
+
+        assert!(registry.kick(1, 77).await);
+        let mut msg = peer1.next().await.unwrap().unwrap();
+        assert_eq!(msg.command, command::DISCONNECT);
+        assert_eq!(msg.read_int().unwrap(), 77);
+    }
+
+    #[tokio::test]
+    async fn kick_is_pending_until_server_registers() {
+        let registry = ServerRegistry::new();
+        assert!(!registry.kick(3, 5).await);
+        assert!(!registry.kick(3, 5).await);
+        assert_eq!(registry.pending_kicks(3).await, vec![5]);
+
+        let (server, mut peer) = connect_pair(1).await;
+        assert_eq!(registry.register(3, server.handle().clone()).await, vec![5]);
+        assert!(registry.pending_kicks(3).await.is_empty());
+        let mut msg = peer.next().await.unwrap().unwrap();
+        assert_eq!(msg.read_int().unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn unregister_ignores_newer_session() {
+        let registry = ServerRegistry::new();
+        let (old, _old_peer) = connect_pair(1).await;
+        let (new, _new_peer) = connect_pair(2).await;
+        registry.register(1, old.handle().clone()).await;
+        registry.register(1, new.handle().clone()).await;
+
+        assert!(!registry.unregister(1, old.id).await);
+        assert_eq!(registry.get(1).await.unwrap().id(), 2);
+        assert!(registry.unregister(1, new.id).await);
+        assert!(registry.get(1).await.is_none());
+    }
+}
@@ -1,66 +1,56 @@
-use super::message::Message;
-use super::session::Session;
+use super::packet::{Disconnect, LoginAccount, LoginResponse, ServerMessage, UpdateTimeLogout};
+use super::session::SessionHandle;
 use crate::model::user::User;
 use anyhow::Result;
 
 pub struct Service;
 
+/// `client_id` của SERVER_MESSAGE khi gửi cho mọi client trên game server
+pub const BROADCAST_CLIENT_ID: i32 = -1;
+
 impl Service {
     pub async fn login_successful(
-        session: &mut Session,
+        session: &SessionHandle,
         user: &User,
         client_id: i32,
     ) -> Result<()> {
-        let mut msg = Message::new(1);
-        msg.write_int(client_id);
-        msg.write_byte(0);
-        msg.write_int(user.id);
-        msg.write_bool(user.is_admin);
-        msg.write_bool(user.active);
-        msg.write_int(user.thoi_vang);
-        msg.write_long(user.last_time_login.timestamp_millis());
-        msg.write_long(user.last_time_logout.timestamp_millis());
-
-        if let Some(ref rewards) = user.reward {
-            msg.write_utf(rewards);
-        } else {
-            msg.write_utf("");
-        }
-        msg.write_int(0); // ruby - not used
-        msg.write_int(0); // moc_nap - not used
-        msg.write_int(user.server_login);
-        msg.write_int(0); // is_use_ma_bao_ve - not used
-        msg.write_int(0); // ma_bao_ve - not used
-        msg.write_int(user.tongnap);
-        msg.write_int(user.vnd);
-        session.send_message(&msg).await?;
-        Ok(())
+        let account = LoginAccount {
+            user_id: user.id,
+            is_admin: user.is_admin,
+            active: user.active,
+            thoi_vang: user.thoi_vang,
+            last_time_login: user.last_time_login.timestamp_millis(),
+            last_time_logout: user.last_time_logout.timestamp_millis(),
+            reward: user.reward.clone().unwrap_or_default(),
+            server_login: user.server_login,
+            tongnap: user.tongnap,
+            vnd: user.vnd,
+        };
+        session
+            .send_packet(&LoginResponse::success(client_id, account))
+            .await
+    }
+    pub async fn login_failed(session: &SessionHandle, client_id: i32, reason: &str) -> Result<()> {
+        session
+            .send_packet(&LoginResponse::failed(client_id, reason))
+            .await
     }
-    pub async fn login_failed(session: &mut Session, client_id: i32, reason: &str) -> Result<()> {
-        let mut msg = Message::new(1);
-        msg.write_int(client_id);
-        msg.write_byte(1);
-        msg.write_utf(reason);
-        session.send_message(&msg).await?;
-        Ok(())
+    pub async fn disconnect(session: &SessionHandle, user_id: i32) -> Result<()> {
+        session.send_packet(&Disconnect { user_id }).await
     }
-    pub async fn disconnect(session: &mut Session, user_id: i32) -> Result<()> {
-        let mut msg = Message::new(3);
-        msg.write_int(user_id);
-        session.send_message(&msg).await?;
-        Ok(())
+    pub async fn server_message(session: &SessionHandle, client_id: i32, text: &str) -> Result<()> {
+        session
+            .send_packet(&ServerMessage {
+                client_id,
+                text: text.to_string(),
+            })
+            .await
     }
-    pub async fn server_message(session: &mut Session, client_id: i32, text: &str) -> Result<()> {
-        let mut msg = Message::new(4);
-        msg.write_int(client_id);
-        msg.write_utf(text);
-        session.send_message(&msg).await?;
-        Ok(())
+    /// Thông báo tới mọi người chơi đang ở game server
+    pub async fn broadcast_message(session: &SessionHandle, text: &str) -> Result<()> {
+        Service::server_message(session, BROADCAST_CLIENT_ID, text).await
     }
-    pub async fn update_time_logout(session: &mut Session, user_id: i32) -> Result<()> {
-        let mut msg = Message::new(6);
-        msg.write_int(user_id);
-        session.send_message(&msg).await?;
-        Ok(())
+    pub async fn update_time_logout(session: &SessionHandle, user_id: i32) -> Result<()> {
+        session.send_packet(&UpdateTimeLogout { user_id }).await
     }
 }
@@ -1,196 +1,557 @@
 use anyhow::Result;
+use futures::{SinkExt, StreamExt};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
-use tokio::sync::Mutex;
-
-// use super::controller::Controller;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_util::codec::{FramedRead, FramedWrite};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
+
+use super::codec::MessageCodec;
 use super::message::Message;
+use super::packet::{Packet, Ping};
+
+/// Số message tối đa chờ gửi cho một game server trước khi người gửi phải chờ
+const OUTBOUND_QUEUE_SIZE: usize = 256;
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("session {0} is closed")]
+    Closed(i32),
+    #[error("session {0} received nothing for too long")]
+    IdleTimeout(i32),
+    #[error("session {0} did not finish sending a frame in time")]
+    FrameTimeout(i32),
+    #[error("session {0} stopped reading, write timed out")]
+    WriteTimeout(i32),
+    #[error("session {0} stopped answering PING")]
+    PingTimeout(i32),
+}
+
+/// Giới hạn thời gian đọc của session, `None` là không giới hạn
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SessionTimeouts {
+    /// Đóng session nếu không nhận được frame nào trong khoảng này
+    pub idle: Option<Duration>,
+    /// Đóng session nếu một frame đã bắt đầu mà không nhận đủ trong khoảng này (slowloris),
+    /// hoặc nếu peer không đọc hết một frame gửi đi trong khoảng này
+    pub frame: Option<Duration>,
+    /// Đóng session sau từng này PING liên tiếp không có PONG, 0 là không giới hạn.
+    /// Chỉ áp dụng khi peer đã trả lời PING ít nhất một lần (game server bản cũ không trả lời)
+    pub missed_pings: u32,
+}
+
+/// Handle dùng để gửi message tới một game server từ bất kỳ đâu trong server.
+///
+/// Message được đưa vào hàng đợi của writer task; khi hàng đợi đầy thì `send_message`
+/// sẽ chờ, khi kết nối đã đóng thì trả về `SessionError::Closed`.
+#[derive(Debug, Clone)]
+pub struct SessionHandle {
+    id: i32,
+    tx: mpsc::Sender<Message>,
+    closed: CancellationToken,
+    /// RTT của PING gần nhất (micro giây), 0 là chưa đo được
+    rtt_micros: Arc<AtomicU64>,
+}
+
+impl SessionHandle {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub async fn send_message(&self, msg: Message) -> Result<(), SessionError> {
+        if self.closed.is_cancelled() {
+            return Err(SessionError::Closed(self.id));
+        }
+        self.tx
+            .send(msg)
+            .await
+            .map_err(|_| SessionError::Closed(self.id))
+    }
+    pub async fn send_packet<P: Packet>(&self, packet: &P) -> Result<()> {
+        self.send_message(packet.encode()?).await?;
+        Ok(())
+    }
+
+    pub fn is_connected(&self) -> bool {
+        !self.closed.is_cancelled()
+    }
+
+    /// Round-trip time đo bằng PING/PONG gần nhất
+    pub fn rtt(&self) -> Option<Duration> {
+        match self.rtt_micros.load(Ordering::Relaxed) {
+            0 => None,
+            micros => Some(Duration::from_micros(micros)),
+        }
+    }
+
+    /// Đóng session: writer task gửi nốt các message đã xếp hàng rồi đóng kết nối
+    pub fn close(&self) {
+        self.closed.cancel();
+    }
+
+    /// Chờ tới khi session bị đóng (do lỗi ghi, peer ngắt kết nối hoặc `close`)
+    pub async fn closed(&self) {
+        self.closed.cancelled().await
+    }
+}
+
+type BoxedReader = Box<dyn AsyncRead + Send + Sync + Unpin>;
 
 pub struct Session {
     pub id: i32,
     pub session_name: String,
     server_id: i32,
-    stream: Arc<Mutex<TcpStream>>,
-    connected: Arc<AtomicBool>,
-    send_key_complete: Arc<AtomicBool>,
-    key: Vec<u8>,
-    cur_r: u8,
-    cur_w: u8,
+    reader: FramedRead<BoxedReader, MessageCodec>,
+    handle: SessionHandle,
+    key_queued: bool,
+    auth_nonce: Option<Vec<u8>>,
+    authenticated_server: Option<i32>,
+    timeouts: SessionTimeouts,
+    /// `timeouts.frame` (micro giây) dùng chung với writer task, 0 là không giới hạn
+    write_timeout_micros: Arc<AtomicU64>,
+    last_frame: Instant,
+    /// Thời điểm thấy frame chưa đầy đủ trong buffer
+    partial_since: Option<Instant>,
+    /// PING đang chờ PONG: (nonce, thời điểm gửi)
+    pending_ping: Option<(i64, Instant)>,
+    next_ping_nonce: i64,
+    /// Số PING liên tiếp không nhận được PONG
+    missed_pings: u32,
+    /// Peer đã trả lời PING ít nhất một lần
+    answers_ping: bool,
 }
 
 impl Session {
-    pub fn new(stream: TcpStream, id: i32) -> Self {
-        let session_name = stream
-            .peer_addr()
-            .map(|addr| addr.to_string())
-            .unwrap_or_else(|_| "unknown".to_string());
+    /// Tạo session trên một kết nối bất kỳ (TCP thường hoặc TLS)
+    pub fn new<S>(stream: S, session_name: String, id: i32, key: Vec<u8>) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        let codec = MessageCodec::new(key);
+        let (read_half, write_half) = tokio::io::split(stream);
+        let (tx, rx) = mpsc::channel(OUTBOUND_QUEUE_SIZE);
+        let closed = CancellationToken::new();
+        let writer = FramedWrite::new(write_half, codec.clone());
+        let write_timeout_micros = Arc::new(AtomicU64::new(0));
+        tokio::spawn(write_loop(
+            id,
+            writer,
+            rx,
+            closed.clone(),
+            write_timeout_micros.clone(),
+        ));
 
         Self {
             id,
             session_name,
             server_id: 0,
-            stream: Arc::new(Mutex::new(stream)),
-            connected: Arc::new(AtomicBool::new(true)),
-            send_key_complete: Arc::new(AtomicBool::new(false)),
-            key: b"vmn".to_vec(),
-            cur_r: 0,
-            cur_w: 0,
+            reader: FramedRead::new(Box::new(read_half) as BoxedReader, codec),
+            handle: SessionHandle {
+                id,
+                tx,
+                closed,
+                rtt_micros: Arc::new(AtomicU64::new(0)),
+            },
+            key_queued: false,
+            auth_nonce: None,
+            authenticated_server: None,
+            timeouts: SessionTimeouts::default(),
+            write_timeout_micros,
+            last_frame: Instant::now(),
+            partial_since: None,
+            pending_ping: None,
+            next_ping_nonce: 1,
+            missed_pings: 0,
+            answers_ping: false,
         }
     }
 
-    fn read_key(&mut self, b: u8) -> u8 {
-        let result = (self.key[self.cur_r as usize] & 0xFF) ^ (b & 0xFF);
-        self.cur_r = (self.cur_r + 1) % self.key.len() as u8;
-        result
+    pub fn with_timeouts(mut self, timeouts: SessionTimeouts) -> Self {
+        let write_timeout = timeouts.frame.map_or(0, |frame| frame.as_micros() as u64);
+        self.write_timeout_micros
+            .store(write_timeout, Ordering::Relaxed);
+        self.timeouts = timeouts;
+        self
     }
 
-    fn write_key(&mut self, b: u8) -> u8 {
-        let result = (self.key[self.cur_w as usize] & 0xFF) ^ (b & 0xFF);
-        self.cur_w = (self.cur_w + 1) % self.key.len() as u8;
-        result
+    pub fn handle(&self) -> &SessionHandle {
+        &self.handle
     }
 
     pub async fn send_key(&mut self) -> Result<()> {
-        if !self.send_key_complete.load(Ordering::Relaxed) {
-            let mut msg = Message::new(-27);
-            msg.write_byte(self.key.len() as i8);
-            msg.write_byte(self.key[0] as i8);
+        if !self.key_queued {
+            self.key_queued = true;
+            let msg = self.reader.decoder().key_message();
+            self.handle.send_message(msg).await?;
+        }
+        Ok(())
+    }
 
-            for i in 1..self.key.len() {
-                msg.write_byte((self.key[i] ^ self.key[i - 1]) as i8);
+    /// Đọc message tiếp theo, trả về `None` khi peer đóng kết nối hoặc session bị đóng.
+    /// Trả về `SessionError::IdleTimeout` / `FrameTimeout` khi vượt `SessionTimeouts`.
+    pub async fn read_message(&mut self) -> Result<Option<Message>> {
+        loop {
+            let check = self.next_check(Instant::now());
+            tokio::select! {
+                frame = self.reader.next() => {
+                    self.partial_since = None;
+                    return match frame {
+                        Some(msg) => {
+                            self.last_frame = Instant::now();
+                            Ok(Some(msg?))
+                        }
+                        None => Ok(None),
+                    };
+                }
+                _ = self.handle.closed() => return Ok(None),
+                _ = sleep_until(check) => {
+                    let now = Instant::now();
+                    if let Some(timeout) = self.timed_out(now) {
+                        return Err(timeout.into());
+                    }
+                    if self.partial_since.is_none() && !self.reader.read_buffer().is_empty() {
+                        self.partial_since = Some(now);
+                    }
+                }
             }
-            self.do_send_message(&msg).await?;
-            self.send_key_complete.store(true, Ordering::Relaxed);
         }
-        Ok(())
     }
 
-    async fn do_send_message(&mut self, msg: &Message) -> Result<()> {
-        let data = msg.get_data();
-        let value = msg.command;
-        let num = data.len();
-        let is_encrypted = self.send_key_complete.load(Ordering::Relaxed);
+    /// Lần kiểm tra timeout tiếp theo, `None` nếu không bật timeout nào
+    fn next_check(&self, now: Instant) -> Option<Instant> {
+        let idle = self.timeouts.idle.map(|idle| self.last_frame + idle);
+        let frame = self.timeouts.frame.map(|frame| match self.partial_since {
+            Some(since) => since + frame,
+            // Chưa thấy frame dở: kiểm tra buffer định kỳ
+            None => now + frame / 2,
+        });
+        idle.into_iter().chain(frame).min()
+    }
 
-        // STEP 1: Encrypt ALL bytes BEFORE locking stream
-        let encrypted_cmd = if is_encrypted {
-            self.write_key(value as u8)
-        } else {
-            value as u8
-        };
+    fn timed_out(&self, now: Instant) -> Option<SessionError> {
+        if let (Some(frame), Some(since)) = (self.timeouts.frame, self.partial_since)
+            && now >= since + frame
+        {
+            return Some(SessionError::FrameTimeout(self.id));
+        }
+        if let Some(idle) = self.timeouts.idle
+            && now >= self.last_frame + idle
+        {
+            return Some(SessionError::IdleTimeout(self.id));
+        }
+        None
+    }
 
-        let (size_byte1, size_byte2) = if is_encrypted {
-            let b1 = self.write_key(((num >> 8) & 0xFF) as u8);
-            let b2 = self.write_key((num & 0xFF) as u8);
-            (b1, b2)
-        } else {
-            (0, 0)
-        };
+    /// Gửi PING, RTT được tính khi nhận PONG cùng nonce.
+    /// Trả về `SessionError::PingTimeout` khi peer bỏ lỡ quá `SessionTimeouts::missed_pings` PING
+    pub async fn send_ping(&mut self) -> Result<()> {
+        if self.pending_ping.is_some() {
+            self.missed_pings += 1;
+        }
+        let limit = self.timeouts.missed_pings;
+        if self.answers_ping && limit > 0 && self.missed_pings >= limit {
+            return Err(SessionError::PingTimeout(self.id).into());
+        }
+        let nonce = self.next_ping_nonce;
+        self.next_ping_nonce += 1;
+        self.pending_ping = Some((nonce, Instant::now()));
+        self.handle.send_packet(&Ping { nonce }).await
+    }
 
-        let mut encrypted_data = data.to_vec();
-        if is_encrypted {
-            for byte in encrypted_data.iter_mut() {
-                *byte = self.write_key(*byte);
-            }
+    /// Ghi nhận PONG, trả về RTT nếu khớp với PING đang chờ
+    pub fn record_pong(&mut self, nonce: i64) -> Option<Duration> {
+        let (expected, sent_at) = self.pending_ping?;
+        if expected != nonce {
+            return None;
         }
+        self.pending_ping = None;
+        self.missed_pings = 0;
+        self.answers_ping = true;
+        let rtt = sent_at.elapsed();
+        self.handle
+            .rtt_micros
+            .store((rtt.as_micros() as u64).max(1), Ordering::Relaxed);
+        Some(rtt)
+    }
 
-        // STEP 2: NOW lock stream and write
-        let mut stream = self.stream.lock().await;
+    pub fn rtt(&self) -> Option<Duration> {
+        self.handle.rtt()
+    }
 
-        stream.write_u8(encrypted_cmd).await?;
+    pub fn is_connected(&self) -> bool {
+        self.handle.is_connected()
+    }
+    pub async fn send_message(&self, msg: Message) -> Result<()> {
+        self.handle.send_message(msg).await?;
+        Ok(())
+    }
+    pub async fn send_packet<P: Packet>(&self, packet: &P) -> Result<()> {
+        self.handle.send_packet(packet).await
+    }
 
-        if is_encrypted {
-            stream.write_u8(size_byte1).await?;
-            stream.write_u8(size_byte2).await?;
-        } else {
-            stream.write_u16(num as u16).await?;
-        }
+    pub fn server_id(&self) -> i32 {
+        self.server_id
+    }
 
-        stream.write_all(&encrypted_data).await?;
-        stream.flush().await?;
+    pub fn set_server_id(&mut self, server_id: i32) {
+        self.server_id = server_id;
+    }
 
-        Ok(())
+    /// Nonce đã gửi trong AUTH_CHALLENGE, chỉ dùng được một lần
+    pub fn set_auth_nonce(&mut self, nonce: Vec<u8>) {
+        self.auth_nonce = Some(nonce);
     }
 
-    pub async fn read_message(&mut self) -> Result<Option<Message>> {
-        let is_encrypted = self.send_key_complete.load(Ordering::Relaxed);
-
-        let (raw_cmd, raw_size_bytes, size, raw_data) = {
-            let mut stream = self.stream.lock().await;
-
-            let cmd = stream.read_u8().await?;
-
-            let (size_b1, size_b2, raw_size) = if is_encrypted {
-                let b1 = stream.read_u8().await?;
-                let b2 = stream.read_u8().await?;
-                (b1, b2, 0)
-            } else {
-                let sz = stream.read_u16().await? as usize;
-                (0, 0, sz)
-            };
-
-            let data = if is_encrypted {
-                vec![]
-            } else {
-                let mut buf = vec![0u8; raw_size];
-                stream.read_exact(&mut buf).await?;
-                buf
-            };
-
-            (cmd, (size_b1, size_b2), raw_size, data)
-        }; // stream guard dropped here
-
-        let cmd = if is_encrypted {
-            let decrypted = self.read_key(raw_cmd);
-            decrypted
-        } else {
-            raw_cmd
+    pub fn take_auth_nonce(&mut self) -> Option<Vec<u8>> {
+        self.auth_nonce.take()
+    }
+
+    /// server_id đã xác thực thành công, `None` nếu chưa xác thực
+    pub fn authenticated_server(&self) -> Option<i32> {
+        self.authenticated_server
+    }
+
+    pub fn set_authenticated_server(&mut self, server_id: i32) {
+        self.authenticated_server = Some(server_id);
+    }
+
+    pub fn is_key_sent(&self) -> bool {
+        self.reader.decoder().is_key_sent()
+    }
+
+    pub fn close(&self) {
+        self.handle.close();
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn write_loop<W: AsyncWrite + Unpin>(
+    id: i32,
+    mut writer: FramedWrite<W, MessageCodec>,
+    mut rx: mpsc::Receiver<Message>,
+    closed: CancellationToken,
+    timeout_micros: Arc<AtomicU64>,
+) {
+    // Peer ngừng đọc thì send bị treo khi buffer TCP đầy, giới hạn thời gian để còn đóng được kết nối
+    let send = async |writer: &mut FramedWrite<W, MessageCodec>, msg: Message| -> Result<()> {
+        match timeout_micros.load(Ordering::Relaxed) {
+            0 => writer.send(msg).await?,
+            micros => tokio::time::timeout(Duration::from_micros(micros), writer.send(msg))
+                .await
+                .map_err(|_| SessionError::WriteTimeout(id))??,
+        }
+        Ok(())
+    };
+    loop {
+        let msg = tokio::select! {
+            msg = rx.recv() => msg,
+            _ = closed.cancelled() => break,
         };
+        let Some(msg) = msg else { break };
+        if let Err(e) = send(&mut writer, msg).await {
+            error!("Session {} write error: {}", id, e);
+            closed.cancel();
+            return;
+        }
+    }
 
-        // Decrypt size and read data
-        let mut data = if is_encrypted {
-            // Decrypt size bytes to get actual data length
-            let b1 = self.read_key(raw_size_bytes.0);
-            let b2 = self.read_key(raw_size_bytes.1);
-            let actual_size = ((b1 as usize) << 8) | (b2 as usize);
-            println!(
-                "DEBUG: Decrypted size: {} -> {}",
-                ((raw_size_bytes.0 as usize) << 8) | (raw_size_bytes.1 as usize),
-                actual_size
-            );
-
-            // Now read the actual data with correct size
-            let mut stream = self.stream.lock().await;
-            let mut buf = vec![0u8; actual_size];
-            stream.read_exact(&mut buf).await?;
-            drop(stream);
-
-            // Decrypt data
-            for byte in buf.iter_mut() {
-                *byte = self.read_key(*byte);
+    // Gửi nốt các message đã nằm trong hàng đợi trước khi đóng
+    rx.close();
+    while let Some(msg) = rx.recv().await {
+        if send(&mut writer, msg).await.is_err() {
+            break;
+        }
+    }
+    closed.cancel();
+    let _ = writer.get_mut().shutdown().await;
+    debug!("Session {} writer stopped", id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command;
+    use crate::testing::connect_pair;
+    use tokio::net::TcpStream;
+
+    #[tokio::test]
+    async fn handle_clones_push_to_same_peer() {
+        let (session, mut peer) = connect_pair(1).await;
+        let handle = session.handle().clone();
+        let sender = tokio::spawn(async move {
+            for i in 0..10 {
+                let mut msg = Message::new(command::DISCONNECT);
+                msg.write_int(i);
+                handle.send_message(msg).await.unwrap();
             }
+        });
+        sender.await.unwrap();
 
-            buf
-        } else {
-            raw_data
-        };
+        for i in 0..10 {
+            let mut msg = peer.next().await.unwrap().unwrap();
+            assert_eq!(msg.command, command::DISCONNECT);
+            assert_eq!(msg.read_int().unwrap(), i);
+        }
+    }
 
-        Ok(Some(Message::with_data(cmd as i8, data)))
+    #[tokio::test]
+    async fn close_flushes_queue_and_rejects_new_messages() {
+        let (session, mut peer) = connect_pair(1).await;
+        let handle = session.handle().clone();
+        handle
+            .send_message(Message::new(command::LOGOUT))
+            .await
+            .unwrap();
+        session.close();
+
+        assert_eq!(peer.next().await.unwrap().unwrap().command, command::LOGOUT);
+        assert!(peer.next().await.is_none());
+        assert!(!handle.is_connected());
+        assert!(matches!(
+            handle.send_message(Message::new(command::LOGOUT)).await,
+            Err(SessionError::Closed(1))
+        ));
     }
 
-    pub fn is_connected(&self) -> bool {
-        self.connected.load(Ordering::Relaxed)
+    #[tokio::test]
+    async fn read_returns_none_when_peer_disconnects() {
+        let (mut session, peer) = connect_pair(1).await;
+        drop(peer);
+        assert!(session.read_message().await.unwrap().is_none());
     }
-    pub async fn send_message(&mut self, msg: &Message) -> Result<()> {
-        self.do_send_message(msg).await
+
+    async fn read_error(session: &mut Session) -> SessionError {
+        match session.read_message().await {
+            Err(e) => e.downcast::<SessionError>().unwrap(),
+            Ok(_) => panic!("expected a timeout"),
+        }
     }
 
-    pub fn is_key_sent(&self) -> bool {
-        self.send_key_complete.load(Ordering::Relaxed)
+    #[tokio::test]
+    async fn silent_peer_hits_idle_timeout() {
+        let (session, _peer) = connect_pair(1).await;
+        let mut session = session.with_timeouts(SessionTimeouts {
+            idle: Some(Duration::from_millis(200)),
+            frame: None,
+            missed_pings: 0,
+        });
+        assert!(matches!(
+            read_error(&mut session).await,
+            SessionError::IdleTimeout(1)
+        ));
     }
 
-    pub fn close(&self) {
-        self.connected.store(false, Ordering::Relaxed);
+    #[tokio::test]
+    async fn partial_frame_hits_frame_timeout() {
+        let (session, mut peer) = connect_pair(1).await;
+        let mut session = session.with_timeouts(SessionTimeouts {
+            idle: Some(Duration::from_secs(60)),
+            frame: Some(Duration::from_millis(200)),
+            missed_pings: 0,
+        });
+        // Chỉ gửi byte command, không bao giờ gửi độ dài và nội dung
+        peer.get_mut()
+            .write_all(&[command::LOGIN as u8])
+            .await
+            .unwrap();
+        assert!(matches!(
+            read_error(&mut session).await,
+            SessionError::FrameTimeout(1)
+        ));
+    }
+
+    #[tokio::test]
+    async fn peer_that_stops_reading_is_dropped() {
+        let (session, _peer) = connect_pair(1).await;
+        let session = session.with_timeouts(SessionTimeouts {
+            idle: None,
+            frame: Some(Duration::from_millis(200)),
+            missed_pings: 0,
+        });
+        let handle = session.handle().clone();
+        let sender = handle.clone();
+        // Peer không đọc gì nên buffer TCP đầy và writer bị treo
+        tokio::spawn(async move {
+            let payload = vec![0u8; 30_000];
+            loop {
+                let mut msg = Message::new(command::DISCONNECT);
+                msg.write_bytes(&payload).unwrap();
+                if sender.send_message(msg).await.is_err() {
+                    break;
+                }
+            }
+        });
+        tokio::time::timeout(Duration::from_secs(10), handle.closed())
+            .await
+            .expect("writer should give up on a peer that stopped reading");
+    }
+
+    #[tokio::test]
+    async fn pong_records_rtt() {
+        let (mut session, mut peer) = connect_pair(1).await;
+        assert!(session.rtt().is_none());
+        session.send_ping().await.unwrap();
+
+        let mut msg = peer.next().await.unwrap().unwrap();
+        assert_eq!(msg.command, command::PING);
+        let Ping { nonce } = Ping::decode(&mut msg).unwrap();
+        assert!(session.record_pong(nonce + 1).is_none());
+        assert!(session.record_pong(nonce).is_some());
+        assert!(session.handle().rtt().is_some());
+        // PONG lặp lại không được tính
+        assert!(session.record_pong(nonce).is_none());
+    }
+
+    async fn answer_ping(
+        session: &mut Session,
+        peer: &mut FramedRead<TcpStream, MessageCodec>,
+    ) -> i64 {
+        session.send_ping().await.unwrap();
+        let mut msg = peer.next().await.unwrap().unwrap();
+        Ping::decode(&mut msg).unwrap().nonce
+    }
+
+    #[tokio::test]
+    async fn peer_that_stops_answering_ping_is_closed() {
+        let (session, mut peer) = connect_pair(1).await;
+        let mut session = session.with_timeouts(SessionTimeouts {
+            missed_pings: 3,
+            ..SessionTimeouts::default()
+        });
+        let nonce = answer_ping(&mut session, &mut peer).await;
+        assert!(session.record_pong(nonce).is_some());
+
+        // Ba PING liên tiếp không có PONG thì PING tiếp theo đóng session
+        for _ in 0..3 {
+            session.send_ping().await.unwrap();
+        }
+        let error = session.send_ping().await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SessionError>(),
+            Some(SessionError::PingTimeout(1))
+        ));
+    }
+
+    #[tokio::test]
+    async fn peer_that_never_answers_ping_is_kept() {
+        let (session, _peer) = connect_pair(1).await;
+        let mut session = session.with_timeouts(SessionTimeouts {
+            missed_pings: 3,
+            ..SessionTimeouts::default()
+        });
+        for _ in 0..10 {
+            session.send_ping().await.unwrap();
+        }
     }
 }
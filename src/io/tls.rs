@@ -0,0 +1,213 @@
+use anyhow::{Context, Result, bail};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+
+use crate::config::TlsConfig;
+
+/// Tạo `TlsAcceptor` từ cert/key PEM, bật mutual TLS nếu có `client_ca_path`
+pub fn build_acceptor(config: &TlsConfig) -> Result<TlsAcceptor> {
+    let provider = Arc::new(ring::default_provider());
+    let certs = load_certs(&config.cert_path)?;
+    let key = load_key(&config.key_path)?;
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+    let builder = match &config.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca_path)? {
+                roots.add(cert)?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .context("invalid client CA")?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let server_config = builder
+        .with_single_cert(certs, key)
+        .context("invalid TLS certificate or key")?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).with_context(|| format!("cannot open {}", path))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("cannot read certificates from {}", path))?;
+    if certs.is_empty() {
+        bail!("no certificate found in {}", path);
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> Result<PrivateKeyDer<'static>> {
+    let file = File::open(path).with_context(|| format!("cannot open {}", path))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .with_context(|| format!("cannot read private key from {}", path))?
+        .with_context(|| format!("no private key found in {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command;
+    use crate::io::codec::{LEGACY_KEY, MessageCodec};
+    use crate::io::message::Message;
+    use crate::io::session::Session;
+    use futures::StreamExt;
+    use rcgen::{BasicConstraints, CertificateParams, CertifiedKey, IsCa, KeyPair};
+    use std::path::PathBuf;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_rustls::TlsConnector;
+    use tokio_rustls::rustls::ClientConfig;
+    use tokio_rustls::rustls::pki_types::{PrivatePkcs8KeyDer, ServerName};
+    use tokio_util::codec::FramedRead;
+
+    fn write_temp(name: &str, content: &str) -> String {
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("login_server_tls_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn client_config(
+        roots: &[CertificateDer<'static>],
+        client: Option<&CertifiedKey>,
+    ) -> ClientConfig {
+        let mut store = RootCertStore::empty();
+        for cert in roots {
+            store.add(cert.clone()).unwrap();
+        }
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(store);
+        match client {
+            Some(certified) => builder
+                .with_client_auth_cert(
+                    vec![certified.cert.der().clone()],
+                    PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()).into(),
+                )
+                .unwrap(),
+            None => builder.with_no_client_auth(),
+        }
+    }
+
+    /// Kết nối TLS tới một Session, trả về phía game server
+    async fn connect(
+        acceptor: TlsAcceptor,
+        client: ClientConfig,
+    ) -> Result<(
+        Session,
+        FramedRead<tokio_rustls::client::TlsStream<TcpStream>, MessageCodec>,
+    )> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, peer) = listener.accept().await?;
+            let stream = acceptor.accept(stream).await?;
+            Ok::<_, anyhow::Error>(Session::new(
+                stream,
+                peer.to_string(),
+                1,
+                LEGACY_KEY.to_vec(),
+            ))
+        });
+        let stream = TcpStream::connect(addr).await?;
+        let client = TlsConnector::from(Arc::new(client))
+            .connect(ServerName::try_from("localhost")?, stream)
+            .await;
+        let session = server.await??;
+        Ok((
+            session,
+            FramedRead::new(client?, MessageCodec::new(LEGACY_KEY.to_vec())),
+        ))
+    }
+
+    #[tokio::test]
+    async fn session_framing_over_self_signed_tls() {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let config = TlsConfig {
+            listen_port: 0,
+            cert_path: write_temp("self_signed.pem", &certified.cert.pem()),
+            key_path: write_temp("self_signed.key", &certified.key_pair.serialize_pem()),
+            client_ca_path: None,
+        };
+        let acceptor = build_acceptor(&config).unwrap();
+        let client = client_config(&[certified.cert.der().clone()], None);
+
+        let (session, mut peer) = connect(acceptor, client).await.unwrap();
+        let mut msg = Message::new(command::DISCONNECT);
+        msg.write_int(12);
+        session.send_message(msg).await.unwrap();
+
+        let mut received = peer.next().await.unwrap().unwrap();
+        assert_eq!(received.command, command::DISCONNECT);
+        assert_eq!(received.read_int().unwrap(), 12);
+    }
+
+    #[tokio::test]
+    async fn mutual_tls_requires_client_certificate() {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let server_key = KeyPair::generate().unwrap();
+        let server_cert = CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .signed_by(&server_key, &ca, &ca_key)
+            .unwrap();
+        let client_key = KeyPair::generate().unwrap();
+        let client_cert = CertificateParams::new(vec!["game-server-1".to_string()])
+            .unwrap()
+            .signed_by(&client_key, &ca, &ca_key)
+            .unwrap();
+
+        let config = TlsConfig {
+            listen_port: 0,
+            cert_path: write_temp("mtls_server.pem", &server_cert.pem()),
+            key_path: write_temp("mtls_server.key", &server_key.serialize_pem()),
+            client_ca_path: Some(write_temp("mtls_ca.pem", &ca.pem())),
+        };
+        let acceptor = build_acceptor(&config).unwrap();
+        let roots = [ca.der().clone()];
+
+        let anonymous = client_config(&roots, None);
+        assert!(connect(acceptor.clone(), anonymous).await.is_err());
+
+        let client = CertifiedKey {
+            cert: client_cert,
+            key_pair: client_key,
+        };
+        let authenticated = client_config(&roots, Some(&client));
+        let (session, mut peer) = connect(acceptor, authenticated).await.unwrap();
+        session
+            .send_message(Message::new(command::LOGOUT))
+            .await
+            .unwrap();
+        assert_eq!(peer.next().await.unwrap().unwrap().command, command::LOGOUT);
+    }
+
+    #[test]
+    fn missing_files_are_reported() {
+        let config = TlsConfig {
+            listen_port: 0,
+            cert_path: "/nonexistent/cert.pem".to_string(),
+            key_path: "/nonexistent/key.pem".to_string(),
+            client_ca_path: None,
+        };
+        let err = build_acceptor(&config).err().unwrap();
+        assert!(err.to_string().contains("/nonexistent/cert.pem"));
+    }
+}
@@ -0,0 +1,13 @@
+pub mod admin;
+pub mod cli;
+pub mod command;
+pub mod config;
+pub mod context;
+pub mod db;
+pub mod io;
+pub mod metrics;
+pub mod model;
+pub mod reload;
+pub mod shutdown;
+#[doc(hidden)]
+pub mod testing;
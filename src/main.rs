@@ -1,25 +1,55 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
-use tracing::{debug, error, info, trace};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{debug, error, info, trace, warn};
 
-mod command;
-mod config;
-mod db;
-mod io;
-mod model;
-
-use config::Config;
-use db::DbManager;
-use io::controller::Controller;
-use io::session::Session;
-use model::user_manager::UserManager;
+use login_server_rust::admin;
+use login_server_rust::cli::{self, Cli};
+use login_server_rust::command;
+use login_server_rust::config::{Config, SharedConfig};
+use login_server_rust::context::AppContext;
+use login_server_rust::db::DbManager;
+use login_server_rust::db::migrate;
+use login_server_rust::io::codec;
+use login_server_rust::io::controller::Controller;
+use login_server_rust::io::link_cleanup::LinkCleanup;
+use login_server_rust::io::message::{self, ProtocolError};
+use login_server_rust::io::server_registry::ServerRegistry;
+use login_server_rust::io::session::{Session, SessionTimeouts};
+use login_server_rust::io::tls;
+use login_server_rust::metrics::{self, Metrics};
+use login_server_rust::model::login_history::LoginHistory;
+use login_server_rust::model::login_limiter::LoginLimiter;
+use login_server_rust::model::maintenance::Maintenance;
+use login_server_rust::model::online_store::OnlineStore;
+use login_server_rust::model::password::PasswordHasher;
+use login_server_rust::model::user_manager::UserManager;
+use login_server_rust::reload;
+use login_server_rust::shutdown;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
-    let config = Config::load("config.toml")?;
-    info!("Configuration loaded");
+    let cli = Cli::parse(std::env::args().skip(1))?;
+    let config_path = cli.config_path.as_str();
+    let config = Config::load(config_path)?;
+    info!("Configuration loaded from {}", config_path);
+
+    let args = &cli.command;
+    match args.first().map(String::as_str) {
+        Some("migrate") => {
+            return migrate_command(&config, args.get(1).map(String::as_str)).await;
+        }
+        Some(other) => bail!("Unknown command: {} ({})", other, cli::USAGE),
+        None => {}
+    }
     debug!("Config: {:#?}", config);
     trace!("Server listen_port: {}", config.server.listen_port);
     trace!("Server second_wait_login: {}", config.server.second_wait_login);
@@ -31,7 +61,8 @@ async fn main() -> Result<()> {
         config.server.second_wait_login
     );
     
-    println!("Database: {}:{}/{} (user: {}, pool: {}-{})",
+    println!("Database: {:?} {}:{}/{} (user: {}, pool: {}-{})",
+        config.database.driver,
         config.database.host,
         config.database.port,
         config.database.database_name,
@@ -39,65 +70,339 @@ async fn main() -> Result<()> {
         config.database.min_connections,
         config.database.max_connections
     );
-    let db = DbManager::new(&config.database).await?;
+    message::set_string_encoding(config.server.string_encoding);
+    let db = DbManager::new(&config.database, &config.login_history).await?;
     info!("Database connected");
 
+    let accounts = db.accounts();
+    match accounts.password_migration_report().await {
+        Ok(report) => info!(
+            "Password migration: {}/{} accounts still store plaintext passwords",
+            report.plaintext, report.total
+        ),
+        Err(e) => warn!("Cannot build password migration report: {}", e),
+    }
+
     let user_manager = UserManager::new();
+    let online_store = config
+        .online_state
+        .enabled
+        .then(|| OnlineStore::new(&config.online_state.path));
+    if let Some(store) = &online_store {
+        match store.restore_into(&user_manager).await {
+            Ok(count) => info!(
+                "Restored {} online users, unconfirmed until their game server sends SET_SERVER",
+                count
+            ),
+            Err(e) => warn!("Cannot restore online users: {:#}", e),
+        }
+    }
+
+    let link_cleanup = LinkCleanup::new(
+        user_manager.clone(),
+        accounts.clone(),
+        Duration::from_secs(config.server.link_grace_secs),
+    );
+    // User nạp lại chưa được xác nhận: coi như game server vừa mất kết nối
+    for server_id in user_manager.unconfirmed_servers().await {
+        link_cleanup.schedule(server_id).await;
+    }
+
+    let context = ServerContext {
+        app: AppContext {
+            link_cleanup,
+            accounts,
+            login_history: LoginHistory::spawn(db.get_pool().clone(), &config.login_history),
+            metrics: Metrics::new(),
+            password_hasher: PasswordHasher::new(&config.password)?,
+            login_limiter: LoginLimiter::new(config.login_limit.clone()),
+            maintenance: Maintenance::new(),
+            user_manager,
+            server_registry: ServerRegistry::new(),
+            config: SharedConfig::new(config.clone()),
+        },
+        next_session_id: Arc::new(AtomicI32::new(0)),
+        sessions: TaskTracker::new(),
+        shutdown: CancellationToken::new(),
+    };
+
+    if let Some(store) = &online_store {
+        store.spawn_saver(
+            context.app.user_manager.clone(),
+            &config.online_state,
+            context.shutdown.clone(),
+        );
+    }
+
+    reload::spawn(config_path, context.app.config.clone(), context.shutdown.clone());
+
+    if config.metrics.enabled {
+        let listener = TcpListener::bind(format!("0.0.0.0:{}", config.metrics.listen_port)).await?;
+        info!("Serving metrics on port: {}", config.metrics.listen_port);
+        let app = context.app.clone();
+        let pool = db.get_pool().clone();
+        let shutdown = context.shutdown.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(listener, app, pool, shutdown).await {
+                error!("Metrics server error: {}", e);
+            }
+        });
+    }
+
+    if config.admin.enabled {
+        let listener = TcpListener::bind(format!("127.0.0.1:{}", config.admin.listen_port)).await?;
+        info!("Serving admin API on 127.0.0.1:{}", config.admin.listen_port);
+        let app = context.app.clone();
+        let token = config.admin.token.clone();
+        let shutdown = context.shutdown.clone();
+        tokio::spawn(async move {
+            if let Err(e) = admin::serve(listener, app, &token, shutdown).await {
+                error!("Admin API error: {}", e);
+            }
+        });
+    }
+
+    let mut tls_task = None;
+    if let Some(tls) = config.server.tls.clone() {
+        let acceptor = tls::build_acceptor(&tls)?;
+        let listener = TcpListener::bind(format!("0.0.0.0:{}", tls.listen_port)).await?;
+        info!("Listening for TLS on port: {}", tls.listen_port);
+        let context = context.clone();
+        tls_task = Some(tokio::spawn(async move {
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        error!("TLS accept error: {}", e);
+                        continue;
+                    }
+                };
+                let acceptor = acceptor.clone();
+                let context = context.clone();
+                tokio::spawn(async move {
+                    // Peer không gửi ClientHello thì không được giữ socket mãi
+                    let timeout = context.app.config.load().session.frame_timeout();
+                    let handshake = acceptor.accept(stream);
+                    let accepted = match timeout {
+                        Some(timeout) => match tokio::time::timeout(timeout, handshake).await {
+                            Ok(accepted) => accepted,
+                            Err(_) => {
+                                warn!("TLS handshake with {} timed out", addr);
+                                return;
+                            }
+                        },
+                        None => handshake.await,
+                    };
+                    match accepted {
+                        Ok(stream) => {
+                            info!("Client {} connected over TLS !", &addr);
+                            context.spawn_session(stream, addr);
+                        }
+                        Err(e) => warn!("TLS handshake with {} failed: {}", addr, e),
+                    }
+                });
+            }
+        }));
+    }
 
     let addr = format!("0.0.0.0:{}", config.server.listen_port);
     let listener = TcpListener::bind(&addr).await?;
     info!("Listening on port: {}", config.server.listen_port);
     println!("@Author dev:Ahwuocdz");
-    let mut session_id = 0;
+    let signal = shutdown::signal();
+    tokio::pin!(signal);
     loop {
-        match listener.accept().await {
-            Ok((stream, addr)) => {
-                info!("Client {} connected !", &addr);
-                let db_clone = db.clone();
-                let user_manager_clone = user_manager.clone();
-                let config_clone = config.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = handle_session(
-                        stream,
-                        session_id,
-                        db_clone,
-                        user_manager_clone,
-                        config_clone,
-                    )
-                    .await
-                    {
-                        error!("Session error: {}", e);
-                    };
-                });
-                session_id += 1;
-            }
-            Err(e) => {
-                error!("Accept error: {}", e);
-            }
+        tokio::select! {
+            _ = &mut signal => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, addr)) => {
+                    info!("Client {} connected !", &addr);
+                    context.spawn_session(stream, addr);
+                }
+                Err(e) => {
+                    error!("Accept error: {}", e);
+                }
+            },
         }
     }
 
+    // 1. Ngừng nhận kết nối mới
+    info!("Shutting down, no longer accepting connections");
+    drop(listener);
+    if let Some(task) = tls_task {
+        task.abort();
+    }
+
+    // 2. Báo cho các game server đang kết nối
+    let config = context.app.config.load();
+    let notified =
+        shutdown::notify_servers(&context.app.server_registry, &config.shutdown.message).await;
+    info!("Notified {} game servers", notified);
+
+    // Chờ các session xử lý xong gói tin đang dở rồi đóng
+    context.shutdown.cancel();
+    context.sessions.close();
+    let drain = Duration::from_secs(config.shutdown.drain_timeout_secs);
+    if tokio::time::timeout(drain, context.sessions.wait())
+        .await
+        .is_err()
+    {
+        warn!(
+            "{} sessions still running after {}s, shutting down anyway",
+            context.sessions.len(),
+            drain.as_secs()
+        );
+    }
+
+    // 3. Ghi last_time_logout cho user còn online
+    let app = &context.app;
+    shutdown::flush_logout_times(&app.user_manager, app.accounts.as_ref()).await;
+    if let Some(store) = &online_store {
+        store.save_now(&app.user_manager).await;
+    }
+    // Ghi nốt lịch sử đăng nhập còn trong hàng đợi trước khi đóng pool
+    app.login_history.close().await;
+
+    // 4. Đóng pool
+    db.close().await;
+    info!("Login server stopped");
     Ok(())
 }
-async fn handle_session(
-    stream: tokio::net::TcpStream,
+
+/// `migrate up` chạy migration còn thiếu, `migrate status` liệt kê trạng thái
+async fn migrate_command(config: &Config, action: Option<&str>) -> Result<()> {
+    let db = DbManager::connect(&config.database).await?;
+    let result = match action.unwrap_or("up") {
+        "up" => migrate::run(db.get_pool()).await.map(|_| {
+            println!("Migrations applied");
+        }),
+        "status" => migrate::status(db.get_pool()).await.map(|migrations| {
+            for migration in migrations {
+                let state = match (migration.applied, migration.checksum_mismatch) {
+                    (true, false) => "applied",
+                    (true, true) => "applied (modified)",
+                    (false, _) => "pending",
+                };
+                println!(
+                    "{:>16}  {:<20} {}",
+                    migration.version, state, migration.description
+                );
+            }
+        }),
+        other => Err(anyhow::anyhow!(
+            "Unknown migrate action: {} (expected up or status)",
+            other
+        )),
+    };
+    db.close().await;
+    result
+}
+
+/// `AppContext` cùng các thành phần chỉ dùng cho vòng đời kết nối
+#[derive(Clone)]
+struct ServerContext {
+    app: AppContext,
+    next_session_id: Arc<AtomicI32>,
+    /// Các task session đang chạy, dùng để chờ khi tắt server
+    sessions: TaskTracker,
+    shutdown: CancellationToken,
+}
+
+impl ServerContext {
+    fn spawn_session<S>(&self, stream: S, addr: SocketAddr)
+    where
+        S: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        let id = self.next_session_id.fetch_add(1, Ordering::Relaxed);
+        let context = self.clone();
+        self.sessions.spawn(async move {
+            if let Err(e) = handle_session(stream, addr.to_string(), id, context).await {
+                error!("Session error: {}", e);
+            };
+        });
+    }
+}
+
+async fn handle_session<S>(
+    stream: S,
+    session_name: String,
     id: i32,
-    db: DbManager,
-    user_manager: UserManager,
-    config: Config,
-) -> Result<()> {
-    let mut session = Session::new(stream, id);
-    let controller = Controller::new(db, user_manager, config);
+    context: ServerContext,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+{
+    let ServerContext { app, shutdown, .. } = context;
+    let server_registry = app.server_registry.clone();
+    let link_cleanup = app.link_cleanup.clone();
+    // Session mới dùng config hiện tại, kể cả sau khi reload
+    let config = app.config.load();
+    let key = if config.server.legacy_xor_key {
+        codec::LEGACY_KEY.to_vec()
+    } else {
+        codec::generate_key()
+    };
+    let session_config = config.session.clone();
+    let mut session = Session::new(stream, session_name, id, key).with_timeouts(SessionTimeouts {
+        idle: session_config.idle_timeout(),
+        frame: session_config.frame_timeout(),
+        missed_pings: session_config.max_missed_pings,
+    });
+    let controller = Controller::new(app);
 
+    let ping_interval = session_config.ping_interval();
+    let result = run_session(&mut session, &controller, &shutdown, ping_interval).await;
+    session.close();
+    // Game server đã gửi SET_SERVER mà mất kết nối: hẹn dọn user của nó
+    if server_registry.unregister(session.server_id(), id).await && !shutdown.is_cancelled() {
+        link_cleanup.schedule(session.server_id()).await;
+    }
+    info!("Session {} disconnected", id);
+    result
+}
+
+async fn run_session(
+    session: &mut Session,
+    controller: &Controller,
+    shutdown: &CancellationToken,
+    ping_interval: Option<Duration>,
+) -> Result<()> {
+    // Khi tắt PING nhánh tick bị vô hiệu, chu kỳ chỉ để tạo interval
+    let period = ping_interval.unwrap_or(Duration::from_secs(3600));
+    let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    ping.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
     while session.is_connected() {
-        match session.read_message().await {
+        // Chỉ dừng giữa hai gói tin, gói đang xử lý luôn được xử lý xong
+        let read = tokio::select! {
+            biased;
+            _ = shutdown.cancelled() => break,
+            _ = ping.tick(), if ping_interval.is_some() => {
+                session.send_ping().await?;
+                continue;
+            }
+            read = session.read_message() => read,
+        };
+        match read {
             Ok(Some(msg)) => {
-                if msg.command == -27 {
+                if msg.command == command::SEND_KEY {
                     info!("Game Server requested encryption key");
                     session.send_key().await?;
+                    controller.send_challenge(session).await?;
                     continue;
                 }
-                controller.process(&mut session, msg).await?;
+                let command = msg.command;
+                if let Err(e) = controller.process(session, msg).await {
+                    // Gói tin hỏng chỉ bỏ qua gói đó, không đóng kết nối với game server
+                    match e.downcast_ref::<ProtocolError>() {
+                        Some(decode_error) => {
+                            warn!(
+                                "Session {} sent malformed command {}: {}",
+                                session.id, command, decode_error
+                            );
+                        }
+                        None => return Err(e),
+                    }
+                }
             }
             Ok(None) => {
                 info!("Connection closed by client");
@@ -109,7 +414,5 @@ async fn handle_session(
             }
         }
     }
-    session.close();
-    info!("Session {} disconnected", id);
     Ok(())
 }
@@ -0,0 +1,222 @@
+use anyhow::Result;
+use axum::Router;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use prometheus::{
+    Histogram, HistogramOpts, HistogramTimer, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::collections::HashMap;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::context::AppContext;
+use crate::db::DbPool;
+use crate::io::server_registry::ServerRegistry;
+use crate::model::login_history::LoginResultCode;
+use crate::model::user_manager::UserManager;
+
+/// Argon2 mất vài chục ms, bucket trải từ 1ms tới 2.5s
+const FIND_BY_CREDENTIALS_BUCKETS: &[f64] =
+    &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+/// Các metric Prometheus của login server, clone rẻ (các metric đều là handle `Arc`)
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    login_results: IntCounterVec,
+    online_users: IntGaugeVec,
+    game_server_sessions: IntGauge,
+    find_by_credentials: Histogram,
+    db_pool_connections: IntGauge,
+    db_pool_idle: IntGauge,
+    db_pool_max: IntGauge,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("login_server".to_string()), None)
+            .expect("valid metrics prefix");
+        let login_results = IntCounterVec::new(
+            Opts::new("login_results_total", "LOGIN requests by result"),
+            &["result"],
+        )
+        .unwrap();
+        let online_users = IntGaugeVec::new(
+            Opts::new("online_users", "Online users per game server"),
+            &["server_id"],
+        )
+        .unwrap();
+        let game_server_sessions = IntGauge::new(
+            "game_server_sessions",
+            "Game server sessions that have sent SET_SERVER",
+        )
+        .unwrap();
+        let find_by_credentials = Histogram::with_opts(
+            HistogramOpts::new(
+                "find_by_credentials_seconds",
+                "Latency of account lookup and password verification",
+            )
+            .buckets(FIND_BY_CREDENTIALS_BUCKETS.to_vec()),
+        )
+        .unwrap();
+        let db_pool_connections =
+            IntGauge::new("db_pool_connections", "Open database connections").unwrap();
+        let db_pool_idle =
+            IntGauge::new("db_pool_idle_connections", "Idle database connections").unwrap();
+        let db_pool_max =
+            IntGauge::new("db_pool_max_connections", "Database pool size limit").unwrap();
+
+        registry.register(Box::new(login_results.clone())).unwrap();
+        registry.register(Box::new(online_users.clone())).unwrap();
+        registry
+            .register(Box::new(game_server_sessions.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(find_by_credentials.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(db_pool_connections.clone()))
+            .unwrap();
+        registry.register(Box::new(db_pool_idle.clone())).unwrap();
+        registry.register(Box::new(db_pool_max.clone())).unwrap();
+
+        Self {
+            registry,
+            login_results,
+            online_users,
+            game_server_sessions,
+            find_by_credentials,
+            db_pool_connections,
+            db_pool_idle,
+            db_pool_max,
+        }
+    }
+
+    pub fn record_login(&self, result: LoginResultCode) {
+        self.login_results
+            .with_label_values(&[result.as_str()])
+            .inc();
+    }
+
+    /// Đo thời gian `User::find_by_credentials`, ghi nhận khi timer bị drop
+    pub fn time_find_by_credentials(&self) -> HistogramTimer {
+        self.find_by_credentials.start_timer()
+    }
+
+    /// Cập nhật gauge online và số game server từ trạng thái hiện tại
+    pub async fn refresh_online(
+        &self,
+        user_manager: &UserManager,
+        server_registry: &ServerRegistry,
+    ) {
+        let mut per_server: HashMap<i32, i64> = HashMap::new();
+        for user in user_manager.all().await {
+            *per_server.entry(user.server_id).or_default() += 1;
+        }
+        // Reset để server đã mất hết user không còn giữ số cũ
+        self.online_users.reset();
+        for (server_id, count) in per_server {
+            self.online_users
+                .with_label_values(&[&server_id.to_string()])
+                .set(count);
+        }
+        self.game_server_sessions
+            .set(server_registry.all().await.len() as i64);
+    }
+
+    pub fn refresh_pool(&self, pool: &DbPool) {
+        let usage = pool.usage();
+        self.db_pool_connections.set(usage.size as i64);
+        self.db_pool_idle.set(usage.idle as i64);
+        self.db_pool_max.set(usage.max as i64);
+    }
+
+    /// Định dạng text của Prometheus
+    pub fn render(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone)]
+struct MetricsState {
+    app: AppContext,
+    pool: DbPool,
+}
+
+/// Phục vụ `GET /metrics` cho tới khi `shutdown` bị hủy
+pub async fn serve(
+    listener: TcpListener,
+    app: AppContext,
+    pool: DbPool,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let router = Router::new()
+        .route("/metrics", get(scrape))
+        .with_state(MetricsState { app, pool });
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await?;
+    Ok(())
+}
+
+async fn scrape(State(state): State<MetricsState>) -> impl IntoResponse {
+    let MetricsState { app, pool } = state;
+    app.metrics
+        .refresh_online(&app.user_manager, &app.server_registry)
+        .await;
+    app.metrics.refresh_pool(&pool);
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        app.metrics.render(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn login_results_are_counted_by_reason() {
+        let metrics = Metrics::new();
+        metrics.record_login(LoginResultCode::Success);
+        metrics.record_login(LoginResultCode::Success);
+        metrics.record_login(LoginResultCode::WrongPassword);
+        drop(metrics.time_find_by_credentials());
+
+        let text = metrics.render();
+        assert!(text.contains("login_server_login_results_total{result=\"success\"} 2"));
+        assert!(text.contains("login_server_login_results_total{result=\"wrong_password\"} 1"));
+        assert!(text.contains("login_server_find_by_credentials_seconds_count 1"));
+    }
+
+    #[tokio::test]
+    async fn online_gauges_follow_user_manager() {
+        let metrics = Metrics::new();
+        let user_manager = UserManager::new();
+        let registry = ServerRegistry::new();
+        user_manager.add(1, "a".to_string(), 1, 10).await;
+        user_manager.add(2, "b".to_string(), 1, 11).await;
+        user_manager.add(3, "c".to_string(), 2, 12).await;
+        metrics.refresh_online(&user_manager, &registry).await;
+
+        let text = metrics.render();
+        assert!(text.contains("login_server_online_users{server_id=\"1\"} 2"));
+        assert!(text.contains("login_server_online_users{server_id=\"2\"} 1"));
+        assert!(text.contains("login_server_game_server_sessions 0"));
+
+        user_manager.remove_all_with_server_id(2).await;
+        metrics.refresh_online(&user_manager, &registry).await;
+        assert!(!metrics.render().contains("server_id=\"2\""));
+    }
+}
@@ -0,0 +1,363 @@
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+#[cfg(feature = "postgres")]
+use sqlx::{PgPool, Postgres};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+use crate::config::LoginHistoryConfig;
+use crate::db::DbPool;
+
+/// Độ dài (ký tự) của cột `username` và `peer_addr` trong migration
+const USERNAME_MAX_CHARS: usize = 64;
+const PEER_ADDR_MAX_CHARS: usize = 64;
+
+/// Kết quả của một lần LOGIN, lưu vào cột `result` của `login_history`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginResultCode {
+    Success,
+    WrongPassword,
+    WrongServer,
+    AlreadyOnline,
+    Cooldown,
+    Testmode,
+    Maintenance,
+    Banned,
+    Locked,
+    Error,
+}
+
+impl LoginResultCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginResultCode::Success => "success",
+            LoginResultCode::WrongPassword => "wrong_password",
+            LoginResultCode::WrongServer => "wrong_server",
+            LoginResultCode::AlreadyOnline => "already_online",
+            LoginResultCode::Cooldown => "cooldown",
+            LoginResultCode::Testmode => "testmode",
+            LoginResultCode::Maintenance => "maintenance",
+            LoginResultCode::Banned => "banned",
+            LoginResultCode::Locked => "locked",
+            LoginResultCode::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginAttempt {
+    pub account_id: Option<i32>,
+    pub username: String,
+    pub server_id: i32,
+    pub client_id: i32,
+    pub peer_addr: String,
+    pub result: LoginResultCode,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Ghi lịch sử đăng nhập qua hàng đợi, một task nền gom thành batch rồi INSERT
+#[derive(Clone, Default)]
+pub struct LoginHistory {
+    /// Sender dùng chung cho mọi bản clone, `close` lấy ra để đóng hàng đợi
+    tx: Arc<RwLock<Option<mpsc::Sender<LoginAttempt>>>>,
+    writer: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl LoginHistory {
+    /// Tạo writer ghi vào bảng `login_history` của `pool`
+    pub fn spawn(pool: DbPool, config: &LoginHistoryConfig) -> Self {
+        Self::spawn_with(config, move |batch| {
+            let pool = pool.clone();
+            async move {
+                match &pool {
+                    DbPool::MySql(pool) => insert_batch_mysql(pool, &batch).await,
+                    #[cfg(feature = "postgres")]
+                    DbPool::Postgres(pool) => insert_batch_postgres(pool, &batch).await,
+                }
+            }
+        })
+    }
+
+    /// Như `spawn` nhưng mỗi batch được ghi bằng `flush`
+    pub fn spawn_with<F, Fut>(config: &LoginHistoryConfig, flush: F) -> Self
+    where
+        F: Fn(Vec<LoginAttempt>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), sqlx::Error>> + Send + 'static,
+    {
+        if !config.enabled {
+            return Self::disabled();
+        }
+        let (tx, rx) = mpsc::channel(config.queue_size);
+        let writer = tokio::spawn(run_writer(
+            rx,
+            config.batch_size,
+            Duration::from_millis(config.flush_interval_ms),
+            flush,
+        ));
+        Self {
+            tx: Arc::new(RwLock::new(Some(tx))),
+            writer: Arc::new(Mutex::new(Some(writer))),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Đóng hàng đợi rồi chờ writer ghi nốt các bản ghi còn lại.
+    /// Gọi trước khi đóng pool, các lần `record` sau đó bị bỏ qua.
+    pub async fn close(&self) {
+        self.tx.write().take();
+        let writer = self.writer.lock().take();
+        if let Some(writer) = writer
+            && let Err(e) = writer.await
+        {
+            error!("Login history writer failed: {}", e);
+        }
+    }
+
+    /// Không chờ: nếu hàng đợi đầy thì bỏ bản ghi thay vì làm chậm LOGIN
+    pub fn record(&self, mut attempt: LoginAttempt) {
+        // Username do game server gửi lên không giới hạn độ dài, một giá trị quá cột
+        // làm hỏng cả câu INSERT nhiều dòng (mất lịch sử của cả batch)
+        truncate_chars(&mut attempt.username, USERNAME_MAX_CHARS);
+        truncate_chars(&mut attempt.peer_addr, PEER_ADDR_MAX_CHARS);
+        let tx = self.tx.read();
+        let Some(tx) = tx.as_ref() else { return };
+        match tx.try_send(attempt) {
+            Ok(()) => {}
+            Err(TrySendError::Full(attempt)) => warn!(
+                "Login history queue full, dropped attempt of {}",
+                attempt.username
+            ),
+            Err(TrySendError::Closed(attempt)) => warn!(
+                "Login history writer stopped, dropped attempt of {}",
+                attempt.username
+            ),
+        }
+    }
+}
+
+fn truncate_chars(value: &mut String, max_chars: usize) {
+    if let Some((end, _)) = value.char_indices().nth(max_chars) {
+        value.truncate(end);
+    }
+}
+
+/// Gom bản ghi tới khi đủ `batch_size` hoặc hết `interval` rồi gọi `flush`.
+/// Kết thúc (sau khi flush phần còn lại) khi mọi sender đã bị drop.
+pub async fn run_writer<F, Fut>(
+    mut rx: mpsc::Receiver<LoginAttempt>,
+    batch_size: usize,
+    interval: Duration,
+    flush: F,
+) where
+    F: Fn(Vec<LoginAttempt>) -> Fut,
+    Fut: Future<Output = Result<(), sqlx::Error>>,
+{
+    let batch_size = batch_size.max(1);
+    let mut ticker = tokio::time::interval(interval);
+    let mut batch = Vec::with_capacity(batch_size);
+    loop {
+        tokio::select! {
+            attempt = rx.recv() => match attempt {
+                Some(attempt) => {
+                    batch.push(attempt);
+                    if batch.len() < batch_size {
+                        continue;
+                    }
+                }
+                None => break,
+            },
+            _ = ticker.tick() => {
+                if batch.is_empty() {
+                    continue;
+                }
+            }
+        }
+        let rows = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+        let count = rows.len();
+        if let Err(e) = flush(rows).await {
+            error!("Failed to write {} login history rows: {}", count, e);
+        }
+    }
+    if !batch.is_empty()
+        && let Err(e) = flush(batch).await
+    {
+        error!("Failed to write final login history rows: {}", e);
+    }
+}
+
+const INSERT_COLUMNS: &str = "INSERT INTO login_history \
+     (account_id, username, server_id, client_id, peer_addr, result, created_at) ";
+
+async fn insert_batch_mysql(pool: &MySqlPool, batch: &[LoginAttempt]) -> Result<(), sqlx::Error> {
+    let mut query: QueryBuilder<MySql> = QueryBuilder::new(INSERT_COLUMNS);
+    query.push_values(batch, |mut row, attempt| {
+        row.push_bind(attempt.account_id)
+            .push_bind(&attempt.username)
+            .push_bind(attempt.server_id)
+            .push_bind(attempt.client_id)
+            .push_bind(&attempt.peer_addr)
+            .push_bind(attempt.result.as_str())
+            .push_bind(attempt.created_at);
+    });
+    query.build().execute(pool).await?;
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+async fn insert_batch_postgres(pool: &PgPool, batch: &[LoginAttempt]) -> Result<(), sqlx::Error> {
+    let mut query: QueryBuilder<Postgres> = QueryBuilder::new(INSERT_COLUMNS);
+    query.push_values(batch, |mut row, attempt| {
+        row.push_bind(attempt.account_id)
+            .push_bind(&attempt.username)
+            .push_bind(attempt.server_id)
+            .push_bind(attempt.client_id)
+            .push_bind(&attempt.peer_addr)
+            .push_bind(attempt.result.as_str())
+            .push_bind(attempt.created_at);
+    });
+    query.build().execute(pool).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn attempt(i: i32) -> LoginAttempt {
+        LoginAttempt {
+            account_id: Some(i),
+            username: format!("user{}", i),
+            server_id: 1,
+            client_id: i,
+            peer_addr: "127.0.0.1:1".to_string(),
+            result: LoginResultCode::Success,
+            created_at: Utc::now(),
+        }
+    }
+
+    async fn collect_batches(
+        rx: mpsc::Receiver<LoginAttempt>,
+        batch_size: usize,
+        interval: Duration,
+    ) -> Vec<Vec<i32>> {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let sink = batches.clone();
+        run_writer(rx, batch_size, interval, move |batch| {
+            let sink = sink.clone();
+            async move {
+                let ids = batch.iter().map(|a| a.client_id).collect();
+                sink.lock().unwrap().push(ids);
+                Ok(())
+            }
+        })
+        .await;
+        Arc::try_unwrap(batches).unwrap().into_inner().unwrap()
+    }
+
+    #[tokio::test]
+    async fn writes_full_batches_then_remainder_on_close() {
+        let (tx, rx) = mpsc::channel(100);
+        for i in 0..7 {
+            tx.send(attempt(i)).await.unwrap();
+        }
+        drop(tx);
+        let batches = collect_batches(rx, 3, Duration::from_secs(3600)).await;
+        assert_eq!(batches, vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flushes_partial_batch_on_interval() {
+        let (tx, rx) = mpsc::channel(100);
+        let writer = tokio::spawn(collect_batches(rx, 100, Duration::from_millis(500)));
+        tx.send(attempt(1)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        tx.send(attempt(2)).await.unwrap();
+        drop(tx);
+        assert_eq!(writer.await.unwrap(), vec![vec![1], vec![2]]);
+    }
+
+    #[tokio::test]
+    async fn close_flushes_pending_attempts() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let sink = written.clone();
+        let config = LoginHistoryConfig {
+            flush_interval_ms: 3_600_000,
+            ..LoginHistoryConfig::default()
+        };
+        let history = LoginHistory::spawn_with(&config, move |batch| {
+            let sink = sink.clone();
+            async move {
+                sink.lock()
+                    .unwrap()
+                    .extend(batch.iter().map(|a| a.client_id));
+                Ok(())
+            }
+        });
+        let clone = history.clone();
+        history.record(attempt(1));
+        clone.record(attempt(2));
+
+        history.close().await;
+        assert_eq!(*written.lock().unwrap(), vec![1, 2]);
+        // Đã đóng thì bỏ qua, không ghi sau khi pool bị đóng
+        clone.record(attempt(3));
+        clone.close().await;
+        assert_eq!(*written.lock().unwrap(), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn oversized_fields_are_truncated_to_column_width() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let sink = written.clone();
+        let history = LoginHistory::spawn_with(&LoginHistoryConfig::default(), move |batch| {
+            let sink = sink.clone();
+            async move {
+                sink.lock().unwrap().extend(batch);
+                Ok(())
+            }
+        });
+        history.record(LoginAttempt {
+            username: "ă".repeat(60_000),
+            peer_addr: "x".repeat(100),
+            ..attempt(1)
+        });
+        history.record(attempt(2));
+        history.close().await;
+
+        let written = written.lock().unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[0].username, "ă".repeat(USERNAME_MAX_CHARS));
+        assert_eq!(written[0].peer_addr.len(), PEER_ADDR_MAX_CHARS);
+        assert_eq!(written[1].username, "user2");
+    }
+
+    #[tokio::test]
+    async fn disabled_history_drops_records() {
+        let batches = Arc::new(Mutex::new(0));
+        let sink = batches.clone();
+        let config = LoginHistoryConfig {
+            enabled: false,
+            ..LoginHistoryConfig::default()
+        };
+        let history = LoginHistory::spawn_with(&config, move |_| {
+            let sink = sink.clone();
+            async move {
+                *sink.lock().unwrap() += 1;
+                Ok(())
+            }
+        });
+        history.record(attempt(1));
+        history.close().await;
+        assert_eq!(*batches.lock().unwrap(), 0);
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::config::LoginLimitConfig;
+
+/// Số username tối đa trong một thế hệ counter, tổng cộng không quá gấp đôi số này
+const MAX_USERNAMES: usize = 10_000;
+
+/// Đếm số lần đăng nhập sai theo username và theo game server, khóa tạm thời
+/// với thời gian tăng gấp đôi sau mỗi lần sai vượt ngưỡng.
+/// `server` là game server của session gửi LOGIN (không phải server_id trong gói tin),
+/// `None` thì chỉ đếm theo username.
+#[derive(Clone)]
+pub struct LoginLimiter {
+    config: LoginLimitConfig,
+    usernames: Arc<RwLock<UsernameCounters>>,
+    servers: Arc<RwLock<HashMap<i32, FailureCounter>>>,
+}
+
+#[derive(Debug, Clone)]
+struct FailureCounter {
+    failures: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl FailureCounter {
+    /// Còn bị khóa bao lâu tính từ `now`
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.locked_until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    fn is_expired(&self, now: Instant, window: Duration) -> bool {
+        self.remaining(now).is_none() && now.duration_since(self.last_failure) >= window
+    }
+}
+
+/// Counter theo username chia làm hai thế hệ. Khi thế hệ hiện tại đầy hoặc đã tồn tại
+/// quá `retention`, thế hệ cũ bị bỏ nguyên khối nên không bao giờ phải quét cả map.
+/// Username bị sai tiếp được chuyển sang thế hệ hiện tại và giữ nguyên counter.
+struct UsernameCounters {
+    current: HashMap<String, FailureCounter>,
+    previous: HashMap<String, FailureCounter>,
+    started_at: Instant,
+}
+
+impl UsernameCounters {
+    fn new() -> Self {
+        Self {
+            current: HashMap::new(),
+            previous: HashMap::new(),
+            started_at: Instant::now(),
+        }
+    }
+
+    fn get(&self, username: &str) -> Option<&FailureCounter> {
+        self.current
+            .get(username)
+            .or_else(|| self.previous.get(username))
+    }
+
+    fn remove(&mut self, username: &str) {
+        self.current.remove(username);
+        self.previous.remove(username);
+    }
+
+    fn entry(
+        &mut self,
+        username: String,
+        now: Instant,
+        retention: Duration,
+    ) -> Entry<'_, String, FailureCounter> {
+        if !self.current.contains_key(&username) {
+            if self.current.len() >= MAX_USERNAMES
+                || now.duration_since(self.started_at) >= retention
+            {
+                self.previous = std::mem::take(&mut self.current);
+                self.started_at = now;
+            }
+            if let Some(counter) = self.previous.remove(&username) {
+                self.current.insert(username.clone(), counter);
+            }
+        }
+        self.current.entry(username)
+    }
+}
+
+impl LoginLimiter {
+    pub fn new(config: LoginLimitConfig) -> Self {
+        Self {
+            config,
+            usernames: Arc::new(RwLock::new(UsernameCounters::new())),
+            servers: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Trả về thời gian còn bị khóa nếu username hoặc game server đang bị khóa
+    pub async fn check(&self, username: &str, server: Option<i32>) -> Option<Duration> {
+        self.check_at(username, server, Instant::now()).await
+    }
+
+    /// Ghi nhận một lần đăng nhập sai
+    pub async fn record_failure(&self, username: &str, server: Option<i32>) {
+        self.record_failure_at(username, server, Instant::now())
+            .await
+    }
+
+    /// Đăng nhập thành công thì xóa counter của username và giảm counter của game server,
+    /// để người chơi gõ nhầm mật khẩu trên server đông người không làm khóa cả server
+    pub async fn record_success(&self, username: &str, server: Option<i32>) {
+        self.usernames
+            .write()
+            .await
+            .remove(&username.to_lowercase());
+        let Some(server_id) = server else { return };
+        let mut servers = self.servers.write().await;
+        if let Some(counter) = servers.get_mut(&server_id) {
+            counter.failures = counter.failures.saturating_sub(1);
+            if counter.failures == 0 && counter.remaining(Instant::now()).is_none() {
+                servers.remove(&server_id);
+            }
+        }
+    }
+
+    async fn check_at(
+        &self,
+        username: &str,
+        server: Option<i32>,
+        now: Instant,
+    ) -> Option<Duration> {
+        if !self.config.enabled {
+            return None;
+        }
+        let by_username = {
+            let usernames = self.usernames.read().await;
+            usernames
+                .get(&username.to_lowercase())
+                .and_then(|counter| counter.remaining(now))
+        };
+        let by_server = match server {
+            Some(server_id) => {
+                let servers = self.servers.read().await;
+                servers
+                    .get(&server_id)
+                    .and_then(|counter| counter.remaining(now))
+            }
+            None => None,
+        };
+        by_username.max(by_server)
+    }
+
+    async fn record_failure_at(&self, username: &str, server: Option<i32>, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+        {
+            let mut usernames = self.usernames.write().await;
+            // Giữ counter ít nhất bằng thời gian khóa dài nhất để lock không bị quên sớm
+            let retention = self
+                .window()
+                .max(Duration::from_secs(self.config.max_lock_secs));
+            let counter = usernames.entry(username.to_lowercase(), now, retention);
+            self.bump(counter, self.config.username_max_failures, now);
+        }
+        let Some(server_id) = server else { return };
+        let mut servers = self.servers.write().await;
+        self.bump(
+            servers.entry(server_id),
+            self.config.server_max_failures,
+            now,
+        );
+    }
+
+    fn bump<K>(&self, entry: Entry<'_, K, FailureCounter>, max_failures: u32, now: Instant) {
+        let window = self.window();
+        let counter = entry.or_insert(FailureCounter {
+            failures: 0,
+            last_failure: now,
+            locked_until: None,
+        });
+        if counter.is_expired(now, window) {
+            counter.failures = 0;
+            counter.locked_until = None;
+        }
+        counter.failures += 1;
+        counter.last_failure = now;
+        if counter.failures >= max_failures {
+            counter.locked_until = Some(now + self.lock_duration(counter.failures - max_failures));
+        }
+    }
+
+    /// base * 2^n, tối đa `max_lock_secs`
+    fn lock_duration(&self, over_threshold: u32) -> Duration {
+        let secs = self
+            .config
+            .base_lock_secs
+            .saturating_mul(1u64 << over_threshold.min(32))
+            .min(self.config.max_lock_secs);
+        Duration::from_secs(secs)
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.failure_window_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> LoginLimiter {
+        LoginLimiter::new(LoginLimitConfig {
+            enabled: true,
+            username_max_failures: 3,
+            server_max_failures: 10,
+            base_lock_secs: 30,
+            max_lock_secs: 100,
+            failure_window_secs: 600,
+        })
+    }
+
+    #[tokio::test]
+    async fn username_is_locked_after_threshold_with_exponential_backoff() {
+        let limiter = limiter();
+        let now = Instant::now();
+        for _ in 0..2 {
+            limiter.record_failure_at("Player", Some(1), now).await;
+        }
+        assert_eq!(limiter.check_at("player", Some(1), now).await, None);
+
+        limiter.record_failure_at("player", Some(1), now).await;
+        assert_eq!(
+            limiter.check_at("PLAYER", Some(1), now).await,
+            Some(Duration::from_secs(30))
+        );
+        limiter.record_failure_at("player", Some(1), now).await;
+        assert_eq!(
+            limiter.check_at("player", Some(1), now).await,
+            Some(Duration::from_secs(60))
+        );
+        // Bị chặn bởi max_lock_secs
+        limiter.record_failure_at("player", Some(1), now).await;
+        assert_eq!(
+            limiter.check_at("player", Some(1), now).await,
+            Some(Duration::from_secs(100))
+        );
+        let later = now + Duration::from_secs(101);
+        assert_eq!(limiter.check_at("player", Some(1), later).await, None);
+    }
+
+    #[tokio::test]
+    async fn success_resets_username_counter() {
+        let limiter = limiter();
+        let now = Instant::now();
+        limiter.record_failure_at("a", Some(1), now).await;
+        limiter.record_failure_at("a", Some(1), now).await;
+        limiter.record_success("a", Some(1)).await;
+        limiter.record_failure_at("a", Some(1), now).await;
+        assert_eq!(limiter.check_at("a", Some(1), now).await, None);
+    }
+
+    #[tokio::test]
+    async fn game_server_is_locked_across_usernames() {
+        let limiter = limiter();
+        let now = Instant::now();
+        for i in 0..10 {
+            limiter
+                .record_failure_at(&format!("user{}", i), Some(7), now)
+                .await;
+        }
+        assert!(
+            limiter
+                .check_at("someone-else", Some(7), now)
+                .await
+                .is_some()
+        );
+        assert!(
+            limiter
+                .check_at("someone-else", Some(8), now)
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn successful_logins_decay_server_counter() {
+        let limiter = limiter();
+        let now = Instant::now();
+        // Mỗi lần gõ sai đều được bù bằng một lần đăng nhập đúng
+        for i in 0..50 {
+            let username = format!("user{}", i);
+            limiter.record_failure_at(&username, Some(7), now).await;
+            limiter.record_success(&username, Some(7)).await;
+        }
+        assert!(
+            limiter
+                .check_at("someone-else", Some(7), now)
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_server_only_counts_username() {
+        let limiter = limiter();
+        let now = Instant::now();
+        for i in 0..20 {
+            limiter
+                .record_failure_at(&format!("user{}", i), None, now)
+                .await;
+        }
+        assert!(limiter.check_at("someone-else", None, now).await.is_none());
+        assert!(limiter.check_at("user0", None, now).await.is_none());
+        assert!(limiter.servers.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failures_outside_window_are_forgotten() {
+        let limiter = limiter();
+        let now = Instant::now();
+        limiter.record_failure_at("a", Some(1), now).await;
+        limiter.record_failure_at("a", Some(1), now).await;
+        let later = now + Duration::from_secs(601);
+        limiter.record_failure_at("a", Some(1), later).await;
+        assert_eq!(limiter.check_at("a", Some(1), later).await, None);
+    }
+
+    #[tokio::test]
+    async fn username_counters_are_bounded() {
+        let limiter = limiter();
+        let now = Instant::now();
+        for _ in 0..2 {
+            limiter.record_failure_at("target", None, now).await;
+        }
+        for i in 0..MAX_USERNAMES {
+            limiter
+                .record_failure_at(&format!("spray{}", i), None, now)
+                .await;
+        }
+        // Đã sang thế hệ mới nhưng lần sai thứ ba vẫn được cộng dồn
+        limiter.record_failure_at("target", None, now).await;
+        assert!(limiter.check_at("target", None, now).await.is_some());
+
+        for i in 0..MAX_USERNAMES * 3 {
+            limiter
+                .record_failure_at(&format!("more{}", i), None, now)
+                .await;
+        }
+        let usernames = limiter.usernames.read().await;
+        assert!(usernames.current.len() + usernames.previous.len() <= MAX_USERNAMES * 2);
+    }
+
+    #[tokio::test]
+    async fn disabled_limiter_never_locks() {
+        let limiter = LoginLimiter::new(LoginLimitConfig {
+            enabled: false,
+            ..LoginLimitConfig::default()
+        });
+        let now = Instant::now();
+        for _ in 0..100 {
+            limiter.record_failure_at("a", Some(1), now).await;
+        }
+        assert_eq!(limiter.check_at("a", Some(1), now).await, None);
+    }
+}
@@ -0,0 +1,190 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use super::user::User;
+
+/// Thông báo khi bảo trì không có `message` riêng
+pub const DEFAULT_MESSAGE: &str = "Máy chủ đang bảo trì, vui lòng quay lại sau";
+
+/// Trạng thái bảo trì của một game server
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceWindow {
+    /// Gửi cho người chơi bị từ chối, bỏ trống thì dùng `DEFAULT_MESSAGE`
+    pub message: String,
+    /// Tự hết bảo trì sau thời điểm này, `None` là tới khi tắt bằng tay
+    pub until: Option<DateTime<Utc>>,
+    /// Username được vào ngoài tài khoản `is_admin` (không phân biệt hoa thường)
+    pub whitelist: Vec<String>,
+}
+
+impl MaintenanceWindow {
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.until.is_none_or(|until| now < until)
+    }
+
+    fn allows(&self, user: &User) -> bool {
+        user.is_admin
+            || self
+                .whitelist
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&user.username))
+    }
+
+    fn notice(&self) -> String {
+        let message = if self.message.is_empty() {
+            DEFAULT_MESSAGE
+        } else {
+            &self.message
+        };
+        match self.until {
+            Some(until) => format!(
+                "{} (dự kiến xong lúc {})",
+                message,
+                until.format("%H:%M %d/%m/%Y UTC")
+            ),
+            None => message.to_string(),
+        }
+    }
+}
+
+/// Bảo trì theo từng server_id, bật / tắt được khi đang chạy
+#[derive(Clone, Default)]
+pub struct Maintenance {
+    servers: Arc<RwLock<HashMap<i32, MaintenanceWindow>>>,
+}
+
+impl Maintenance {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bật (hoặc thay thế) bảo trì cho `server_id`
+    pub async fn set(&self, server_id: i32, window: MaintenanceWindow) {
+        self.servers.write().await.insert(server_id, window);
+    }
+
+    /// Tắt bảo trì, trả về `false` nếu server không đang bảo trì
+    pub async fn clear(&self, server_id: i32) -> bool {
+        self.servers.write().await.remove(&server_id).is_some()
+    }
+
+    /// Các server đang bảo trì (đã bỏ các lần bảo trì hết hạn)
+    pub async fn all(&self) -> Vec<(i32, MaintenanceWindow)> {
+        let now = Utc::now();
+        let mut servers = self.servers.write().await;
+        servers.retain(|_, window| window.is_active(now));
+        let mut all: Vec<_> = servers
+            .iter()
+            .map(|(server_id, window)| (*server_id, window.clone()))
+            .collect();
+        all.sort_by_key(|(server_id, _)| *server_id);
+        all
+    }
+
+    /// Trả về thông báo từ chối nếu `user` không được vào `server_id` lúc này
+    pub async fn check(&self, server_id: i32, user: &User) -> Option<String> {
+        self.check_at(server_id, user, Utc::now()).await
+    }
+
+    async fn check_at(&self, server_id: i32, user: &User, now: DateTime<Utc>) -> Option<String> {
+        let servers = self.servers.read().await;
+        let window = servers.get(&server_id)?;
+        if !window.is_active(now) || window.allows(user) {
+            return None;
+        }
+        Some(window.notice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::account;
+    use chrono::TimeDelta;
+
+    fn user(username: &str, is_admin: bool) -> User {
+        User {
+            is_admin,
+            ..account(1, username)
+        }
+    }
+
+    #[tokio::test]
+    async fn only_the_server_in_maintenance_is_blocked() {
+        let maintenance = Maintenance::new();
+        maintenance
+            .set(
+                2,
+                MaintenanceWindow {
+                    message: "Cập nhật phiên bản mới".to_string(),
+                    ..MaintenanceWindow::default()
+                },
+            )
+            .await;
+        let player = user("player", false);
+
+        assert_eq!(
+            maintenance.check(2, &player).await.as_deref(),
+            Some("Cập nhật phiên bản mới")
+        );
+        assert!(maintenance.check(1, &player).await.is_none());
+        assert!(maintenance.check(2, &user("gm", true)).await.is_none());
+
+        assert!(maintenance.clear(2).await);
+        assert!(maintenance.check(2, &player).await.is_none());
+        assert!(!maintenance.clear(2).await);
+    }
+
+    #[tokio::test]
+    async fn whitelist_lets_testers_in() {
+        let maintenance = Maintenance::new();
+        maintenance
+            .set(
+                1,
+                MaintenanceWindow {
+                    whitelist: vec!["Tester01".to_string()],
+                    ..MaintenanceWindow::default()
+                },
+            )
+            .await;
+
+        assert!(
+            maintenance
+                .check(1, &user("tester01", false))
+                .await
+                .is_none()
+        );
+        assert_eq!(
+            maintenance
+                .check(1, &user("player", false))
+                .await
+                .as_deref(),
+            Some(DEFAULT_MESSAGE)
+        );
+    }
+
+    #[tokio::test]
+    async fn window_ends_at_until() {
+        let maintenance = Maintenance::new();
+        let now = Utc::now();
+        let until = now + TimeDelta::minutes(30);
+        maintenance
+            .set(
+                1,
+                MaintenanceWindow {
+                    until: Some(until),
+                    ..MaintenanceWindow::default()
+                },
+            )
+            .await;
+        let player = user("player", false);
+
+        let notice = maintenance.check_at(1, &player, now).await.unwrap();
+        assert!(notice.contains("dự kiến xong lúc"), "{}", notice);
+        assert!(maintenance.check_at(1, &player, until).await.is_none());
+    }
+}
@@ -1,2 +1,7 @@
+pub mod login_history;
+pub mod login_limiter;
+pub mod maintenance;
+pub mod online_store;
+pub mod password;
 pub mod user;
 pub mod user_manager;
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use super::user_manager::{UserInfo, UserManager};
+use crate::config::OnlineStateConfig;
+
+/// Lưu danh sách user online ra file JSON để nạp lại sau khi restart
+#[derive(Debug, Clone)]
+pub struct OnlineStore {
+    path: PathBuf,
+}
+
+impl OnlineStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Đọc danh sách đã lưu, file chưa tồn tại thì trả về rỗng
+    pub async fn load(&self) -> Result<Vec<UserInfo>> {
+        let content = match tokio::fs::read(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("cannot read {}", self.path.display()));
+            }
+        };
+        serde_json::from_slice(&content)
+            .with_context(|| format!("invalid online state in {}", self.path.display()))
+    }
+
+    /// Ghi ra file tạm rồi rename để không bao giờ để lại file ghi dở
+    pub async fn save(&self, users: &[UserInfo]) -> Result<()> {
+        let content = serde_json::to_vec(users)?;
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, content)
+            .await
+            .with_context(|| format!("cannot write {}", tmp.display()))?;
+        tokio::fs::rename(&tmp, &self.path)
+            .await
+            .with_context(|| format!("cannot replace {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// Nạp danh sách đã lưu vào `user_manager`, trả về số user nạp được
+    pub async fn restore_into(&self, user_manager: &UserManager) -> Result<usize> {
+        let saved = self.load().await?;
+        let count = saved.len();
+        user_manager.restore(saved).await;
+        Ok(count)
+    }
+
+    /// Lưu lại mỗi khi danh sách thay đổi, kiểm tra theo `config.save_interval_secs`.
+    /// Dừng khi `shutdown` bị hủy (lần lưu cuối do nơi gọi thực hiện).
+    pub fn spawn_saver(
+        &self,
+        user_manager: UserManager,
+        config: &OnlineStateConfig,
+        shutdown: CancellationToken,
+    ) {
+        let store = self.clone();
+        let interval = Duration::from_secs(config.save_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut saved_version = user_manager.version();
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                let version = user_manager.version();
+                if version == saved_version {
+                    continue;
+                }
+                match store.save(&user_manager.all().await).await {
+                    Ok(()) => saved_version = version,
+                    Err(e) => warn!("Cannot save online users: {:#}", e),
+                }
+            }
+        });
+    }
+
+    /// Lưu ngay (khi tắt server)
+    pub async fn save_now(&self, user_manager: &UserManager) {
+        let users = user_manager.all().await;
+        match self.save(&users).await {
+            Ok(()) => info!(
+                "Saved {} online users to {}",
+                users.len(),
+                self.path.display()
+            ),
+            Err(e) => warn!("Cannot save online users: {:#}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> OnlineStore {
+        let dir = std::env::temp_dir().join(format!("login_server_online_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = std::fs::remove_file(&path);
+        OnlineStore::new(path)
+    }
+
+    #[tokio::test]
+    async fn missing_file_loads_empty() {
+        let store = temp_store("missing.json");
+        assert!(store.load().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn restored_users_are_unconfirmed_until_set_server() {
+        let store = temp_store("round_trip.json");
+        let before_restart = UserManager::new();
+        before_restart.add(1, "a".to_string(), 1, 10).await;
+        before_restart.add(2, "b".to_string(), 2, 20).await;
+        store.save(&before_restart.all().await).await.unwrap();
+
+        let user_manager = UserManager::new();
+        assert_eq!(store.restore_into(&user_manager).await.unwrap(), 2);
+        let restored = user_manager.find(1).await.unwrap();
+        assert_eq!((restored.server_id, restored.client_id), (1, 10));
+        assert!(!restored.confirmed);
+        assert_eq!(user_manager.unconfirmed_servers().await, vec![1, 2]);
+
+        // Game server 1 gửi lại SET_SERVER
+        user_manager.remove_all_with_server_id(1).await;
+        user_manager.add(1, "a".to_string(), 1, 11).await;
+        assert!(user_manager.find(1).await.unwrap().confirmed);
+        assert_eq!(user_manager.unconfirmed_servers().await, vec![2]);
+    }
+
+    #[tokio::test]
+    async fn corrupt_file_is_reported() {
+        let store = temp_store("corrupt.json");
+        std::fs::write(&store.path, b"{not json").unwrap();
+        let err = store.load().await.unwrap_err();
+        assert!(format!("{:#}", err).contains("corrupt.json"));
+    }
+}
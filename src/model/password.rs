@@ -0,0 +1,132 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, SaltString};
+use argon2::{Algorithm, Argon2, Params, PasswordHasher as _, PasswordVerifier, Version};
+use subtle::ConstantTimeEq;
+
+use crate::config::PasswordConfig;
+
+/// Kết quả kiểm tra mật khẩu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    Invalid,
+    Valid,
+    /// Đúng mật khẩu nhưng cần hash lại (mật khẩu plaintext cũ hoặc tham số hash đã đổi)
+    ValidNeedsRehash,
+}
+
+/// Hash / kiểm tra mật khẩu bằng Argon2id, tham số lấy từ `[password]` trong config
+#[derive(Clone)]
+pub struct PasswordHasher {
+    argon2: Argon2<'static>,
+}
+
+impl PasswordHasher {
+    pub fn new(config: &PasswordConfig) -> anyhow::Result<Self> {
+        let params = Params::new(
+            config.memory_kib,
+            config.iterations,
+            config.parallelism,
+            None,
+        )
+        .map_err(|e| anyhow::anyhow!("invalid argon2 params: {}", e))?;
+        Ok(Self {
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+        })
+    }
+
+    pub fn hash(&self, password: &str) -> anyhow::Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = self
+            .argon2
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| anyhow::anyhow!("argon2 hash failed: {}", e))?;
+        Ok(hash.to_string())
+    }
+
+    /// So sánh `password` với giá trị đang lưu trong DB (hash PHC hoặc plaintext cũ)
+    pub fn verify(&self, stored: &str, password: &str) -> Verification {
+        if !is_hashed(stored) {
+            return if bool::from(stored.as_bytes().ct_eq(password.as_bytes())) {
+                Verification::ValidNeedsRehash
+            } else {
+                Verification::Invalid
+            };
+        }
+        let Ok(parsed) = PasswordHash::new(stored) else {
+            return Verification::Invalid;
+        };
+        if self
+            .argon2
+            .verify_password(password.as_bytes(), &parsed)
+            .is_err()
+        {
+            return Verification::Invalid;
+        }
+        let configured = self.argon2.params();
+        let same_params = Params::try_from(&parsed).is_ok_and(|params| {
+            params.m_cost() == configured.m_cost()
+                && params.t_cost() == configured.t_cost()
+                && params.p_cost() == configured.p_cost()
+        });
+        if parsed.algorithm != Algorithm::Argon2id.ident() || !same_params {
+            Verification::ValidNeedsRehash
+        } else {
+            Verification::Valid
+        }
+    }
+}
+
+/// Giá trị trong cột `password` đã là hash Argon2 (định dạng PHC) hay chưa
+pub fn is_hashed(stored: &str) -> bool {
+    stored.starts_with("$argon2")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hasher(iterations: u32) -> PasswordHasher {
+        PasswordHasher::new(&PasswordConfig {
+            memory_kib: 1024,
+            iterations,
+            parallelism: 1,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn hash_then_verify() {
+        let hasher = hasher(1);
+        let hash = hasher.hash("mật khẩu").unwrap();
+        assert!(is_hashed(&hash));
+        assert_eq!(hasher.verify(&hash, "mật khẩu"), Verification::Valid);
+        assert_eq!(hasher.verify(&hash, "sai"), Verification::Invalid);
+    }
+
+    #[test]
+    fn plaintext_is_accepted_once_and_flagged() {
+        let hasher = hasher(1);
+        assert_eq!(
+            hasher.verify("123456", "123456"),
+            Verification::ValidNeedsRehash
+        );
+        assert_eq!(hasher.verify("123456", "1234567"), Verification::Invalid);
+    }
+
+    #[test]
+    fn changed_params_request_rehash() {
+        let hash = hasher(1).hash("abc").unwrap();
+        assert_eq!(
+            hasher(2).verify(&hash, "abc"),
+            Verification::ValidNeedsRehash
+        );
+    }
+
+    #[test]
+    fn malformed_hash_is_invalid() {
+        assert_eq!(
+            hasher(1).verify("$argon2id$broken", "abc"),
+            Verification::Invalid
+        );
+    }
+}
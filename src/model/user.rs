@@ -1,9 +1,14 @@
 use chrono::{DateTime, Utc};
 use sqlx::FromRow;
+use std::fmt;
 
-#[derive(Debug, FromRow)]
+use super::password::{PasswordHasher, Verification};
+use crate::db::account::AccountRepository;
+
+#[derive(FromRow, Clone)]
 pub struct User {
     pub id: i32,
+    pub username: String,
     pub is_admin: bool,
     pub active: bool,
     pub thoi_vang: i32,
@@ -14,40 +19,138 @@ pub struct User {
     pub last_time_logout: DateTime<Utc>,
     pub reward: Option<String>,
     pub ban: bool,
+    /// Hash Argon2 (PHC) hoặc plaintext với tài khoản chưa được migrate
+    pub password: String,
+}
+
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("username", &self.username)
+            .field("is_admin", &self.is_admin)
+            .field("active", &self.active)
+            .field("thoi_vang", &self.thoi_vang)
+            .field("vnd", &self.vnd)
+            .field("tongnap", &self.tongnap)
+            .field("server_login", &self.server_login)
+            .field("last_time_login", &self.last_time_login)
+            .field("last_time_logout", &self.last_time_logout)
+            .field("reward", &self.reward)
+            .field("ban", &self.ban)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Số tài khoản còn lưu mật khẩu plaintext
+#[derive(Debug, Clone, Copy)]
+pub struct PasswordMigrationReport {
+    pub total: i64,
+    pub plaintext: i64,
 }
+
 impl User {
+    /// Tìm tài khoản và kiểm tra mật khẩu trong Rust.
+    /// Mật khẩu plaintext cũ được hash lại ngay sau khi đăng nhập đúng.
     pub async fn find_by_credentials(
-        pool: &sqlx::MySqlPool,
+        accounts: &dyn AccountRepository,
+        hasher: &PasswordHasher,
         username: &str,
         password: &str,
-    ) -> Result<Option<User>, sqlx::Error> {
-        sqlx::query_as::<_, User>(
-            "SELECT * FROM account WHERE username = ? AND password = ? LIMIT 1",
-        )
-        .bind(username)
-        .bind(password)
-        .fetch_optional(pool)
-        .await
+    ) -> anyhow::Result<Option<User>> {
+        let Some(user) = accounts.find_by_username(username).await? else {
+            return Ok(None);
+        };
+
+        // Argon2 tốn CPU, không chạy trên thread của runtime
+        let hasher = hasher.clone();
+        let stored = user.password.clone();
+        let password = password.to_string();
+        let (verification, rehashed) = tokio::task::spawn_blocking(move || {
+            let verification = hasher.verify(&stored, &password);
+            let rehashed =
+                (verification == Verification::ValidNeedsRehash).then(|| hasher.hash(&password));
+            (verification, rehashed)
+        })
+        .await?;
+
+        if verification == Verification::Invalid {
+            return Ok(None);
+        }
+        // Hash lại chỉ là tối ưu: lỗi ghi (ví dụ cột `password` cũ quá hẹp) không được chặn đăng nhập
+        if let Some(hash) = rehashed {
+            let written = match hash {
+                Ok(hash) => accounts.update_password_hash(user.id, &hash).await,
+                Err(e) => Err(e),
+            };
+            match written {
+                Ok(()) => tracing::info!("Rehashed password of account {}", user.id),
+                Err(e) => tracing::warn!("Cannot rehash password of account {}: {:#}", user.id, e),
+            }
+        }
+        Ok(Some(user))
     }
-    pub async fn update_logout_time(
-        pool: &sqlx::MySqlPool,
-        user_id: i32,
-    ) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE account SET last_time_logout = NOW() WHERE id = ?")
-            .bind(user_id)
-            .execute(pool)
-            .await?;
-        Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PasswordConfig;
+    use crate::db::memory::{MemoryAccountRepository, account};
+    use anyhow::{Result, bail};
+    use async_trait::async_trait;
+
+    /// Như `MemoryAccountRepository` nhưng không ghi được hash mới
+    struct ReadOnlyPasswords(MemoryAccountRepository);
+
+    #[async_trait]
+    impl AccountRepository for ReadOnlyPasswords {
+        async fn find_by_username(&self, username: &str) -> Result<Option<User>> {
+            self.0.find_by_username(username).await
+        }
+
+        async fn update_password_hash(&self, _user_id: i32, _hash: &str) -> Result<()> {
+            bail!("Data too long for column 'password'")
+        }
+
+        async fn update_login_time(&self, user_id: i32) -> Result<()> {
+            self.0.update_login_time(user_id).await
+        }
+
+        async fn update_logout_time(&self, user_id: i32) -> Result<()> {
+            self.0.update_logout_time(user_id).await
+        }
+
+        async fn set_ban(&self, user_id: i32, ban: bool) -> Result<bool> {
+            self.0.set_ban(user_id, ban).await
+        }
+
+        async fn password_migration_report(&self) -> Result<PasswordMigrationReport> {
+            self.0.password_migration_report().await
+        }
     }
 
-    pub async fn update_login_time(
-        pool: &sqlx::MySqlPool,
-        user_id: i32,
-    ) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE account SET last_time_login = NOW() WHERE id = ?")
-            .bind(user_id)
-            .execute(pool)
-            .await?;
-        Ok(())
+    #[tokio::test]
+    async fn failed_rehash_does_not_block_login() {
+        let memory = MemoryAccountRepository::new();
+        memory
+            .insert(User {
+                password: "123456".to_string(),
+                ..account(1, "player")
+            })
+            .await;
+        let accounts = ReadOnlyPasswords(memory.clone());
+        let hasher = PasswordHasher::new(&PasswordConfig {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        })
+        .unwrap();
+
+        let user = User::find_by_credentials(&accounts, &hasher, "player", "123456")
+            .await
+            .unwrap();
+        assert_eq!(user.map(|user| user.id), Some(1));
+        assert_eq!(memory.get(1).await.unwrap().password, "123456");
     }
 }
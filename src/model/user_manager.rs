@@ -1,26 +1,74 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::sync::RwLock;
 
 /// UserManager để track users đang online (giống Java version)
 #[derive(Clone)]
 pub struct UserManager {
     users: Arc<RwLock<HashMap<i32, UserInfo>>>,
+    /// Tăng sau mỗi thay đổi, dùng để biết khi nào cần lưu lại danh sách
+    version: Arc<AtomicU64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserInfo {
     pub user_id: i32,
     pub username: String,
     pub server_id: i32,
     pub client_id: i32,
+    /// `false` với user nạp lại từ lần chạy trước, cho tới khi game server gửi lại SET_SERVER
+    #[serde(skip)]
+    pub confirmed: bool,
+}
+
+impl Default for UserManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl UserManager {
     pub fn new() -> Self {
         Self {
             users: Arc::new(RwLock::new(HashMap::new())),
+            version: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Số lần danh sách đã thay đổi
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    fn touch(&self) {
+        self.version.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Nạp lại danh sách đã lưu, đánh dấu chưa xác nhận. Không ghi đè user đã có.
+    pub async fn restore(&self, saved: Vec<UserInfo>) {
+        let mut users = self.users.write().await;
+        for user in saved {
+            users.entry(user.user_id).or_insert(UserInfo {
+                confirmed: false,
+                ..user
+            });
         }
+        self.touch();
+    }
+
+    /// Các server_id còn user chưa được game server xác nhận
+    pub async fn unconfirmed_servers(&self) -> Vec<i32> {
+        let users = self.users.read().await;
+        let mut servers: Vec<i32> = users
+            .values()
+            .filter(|user| !user.confirmed)
+            .map(|user| user.server_id)
+            .collect();
+        servers.sort_unstable();
+        servers.dedup();
+        servers
     }
 
     /// Thêm user vào danh sách online
@@ -33,14 +81,18 @@ impl UserManager {
                 username,
                 server_id,
                 client_id,
+                confirmed: true,
             },
         );
+        self.touch();
     }
 
     /// Xóa user khỏi danh sách online
     pub async fn remove(&self, user_id: i32) {
         let mut users = self.users.write().await;
-        users.remove(&user_id);
+        if users.remove(&user_id).is_some() {
+            self.touch();
+        }
     }
 
     /// Tìm user theo ID
@@ -53,6 +105,13 @@ impl UserManager {
     pub async fn remove_all_with_server_id(&self, server_id: i32) {
         let mut users = self.users.write().await;
         users.retain(|_, user| user.server_id != server_id);
+        self.touch();
+    }
+
+    /// Danh sách toàn bộ user đang online
+    pub async fn all(&self) -> Vec<UserInfo> {
+        let users = self.users.read().await;
+        users.values().cloned().collect()
     }
 
     /// Kiểm tra user có đang online không
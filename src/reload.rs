@@ -0,0 +1,178 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::config::{Config, SharedConfig};
+use crate::io::message;
+
+/// Chu kỳ kiểm tra thời điểm sửa file config
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Đọc lại `path` và thay config đang dùng, trả về `false` nếu nội dung không đổi.
+/// File lỗi thì trả lỗi và giữ nguyên config cũ.
+pub fn reload(path: &str, config: &SharedConfig) -> Result<bool> {
+    let new = Config::load(path)?;
+    let old = config.load();
+    if *old == new {
+        return Ok(false);
+    }
+    for name in old.restart_required(&new) {
+        warn!(
+            "Config `{}` changed, restart the login server to apply it",
+            name
+        );
+    }
+    for name in old.new_connections_only(&new) {
+        warn!(
+            "Config `{}` changed, it applies to new game server connections only",
+            name
+        );
+    }
+    message::set_string_encoding(new.server.string_encoding);
+    config.store(new);
+    Ok(true)
+}
+
+/// Nạp lại config khi nhận SIGHUP hoặc khi file `path` được sửa, dừng khi `shutdown` bị hủy
+pub fn spawn(path: impl Into<PathBuf>, config: SharedConfig, shutdown: CancellationToken) {
+    let path = path.into();
+    tokio::spawn(async move {
+        let mut modified = modified_at(&path).await;
+        let mut ticker = tokio::time::interval(WATCH_INTERVAL);
+        let mut hangup = Hangup::new();
+        loop {
+            let reason = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = hangup.recv() => "SIGHUP",
+                _ = ticker.tick() => {
+                    let current = modified_at(&path).await;
+                    if current == modified {
+                        continue;
+                    }
+                    modified = current;
+                    "file change"
+                }
+            };
+            match reload(&path.to_string_lossy(), &config) {
+                Ok(true) => info!("Config reloaded ({})", reason),
+                Ok(false) => info!("Config unchanged ({})", reason),
+                Err(e) => warn!(
+                    "Rejected invalid {} ({}), keeping the current config: {:#}",
+                    path.display(),
+                    reason,
+                    e
+                ),
+            }
+        }
+    });
+}
+
+async fn modified_at(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+/// SIGHUP trên unix, không bao giờ xảy ra trên nền tảng khác
+struct Hangup {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl Hangup {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+            let signal = signal(SignalKind::hangup())
+                .inspect_err(|e| warn!("Cannot listen for SIGHUP: {}", e))
+                .ok();
+            Self { signal }
+        }
+        #[cfg(not(unix))]
+        {
+            Self {}
+        }
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = &mut self.signal {
+            if signal.recv().await.is_some() {
+                return;
+            }
+            self.signal = None;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::CONFIG;
+
+    fn temp_config(name: &str, content: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("login_server_reload_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn live_settings_are_swapped() {
+        let path = temp_config("live.toml", CONFIG);
+        let config = SharedConfig::new(Config::load(&path).unwrap());
+        let before = config.load();
+
+        std::fs::write(&path, CONFIG.replace("testmode = 0", "testmode = 1")).unwrap();
+        assert!(reload(&path, &config).unwrap());
+        assert_eq!(config.load().server.testmode, 1);
+        // Bản đã lấy trước khi reload vẫn giữ nguyên
+        assert_eq!(before.server.testmode, 0);
+        assert!(!reload(&path, &config).unwrap());
+    }
+
+    #[test]
+    fn invalid_file_keeps_old_config() {
+        let path = temp_config("invalid.toml", CONFIG);
+        let config = SharedConfig::new(Config::load(&path).unwrap());
+
+        std::fs::write(&path, CONFIG.replace("testmode = 0", "testmode = \"yes\"")).unwrap();
+        assert!(reload(&path, &config).is_err());
+        assert_eq!(config.load().server.testmode, 0);
+    }
+
+    #[test]
+    fn restart_only_settings_are_reported() {
+        let old: Config = toml::from_str(CONFIG).unwrap();
+        let new: Config = toml::from_str(
+            &CONFIG
+                .replace("listen_port = 14445", "listen_port = 14446")
+                .replace("max_connections = 10", "max_connections = 20")
+                .replace("second_wait_login = 10", "second_wait_login = 5"),
+        )
+        .unwrap();
+        assert_eq!(
+            old.restart_required(&new),
+            vec!["server.listen_port", "database"]
+        );
+        assert!(old.new_connections_only(&new).is_empty());
+    }
+
+    #[test]
+    fn connection_settings_are_reported() {
+        let old: Config = toml::from_str(CONFIG).unwrap();
+        let new: Config = toml::from_str(&format!(
+            "{}\n[auth]\nenabled = true\n\n[session]\nping_interval_secs = 10\n",
+            CONFIG.replace("testmode = 0", "testmode = 0\nlegacy_xor_key = true")
+        ))
+        .unwrap();
+        assert_eq!(
+            old.new_connections_only(&new),
+            vec!["server.legacy_xor_key", "auth", "session"]
+        );
+        assert!(old.restart_required(&new).is_empty());
+    }
+}
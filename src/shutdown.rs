@@ -0,0 +1,108 @@
+use tracing::{info, warn};
+
+use crate::db::account::AccountRepository;
+use crate::io::server_registry::ServerRegistry;
+use crate::io::service::Service;
+use crate::model::user_manager::UserManager;
+
+/// Chờ Ctrl-C, hoặc SIGTERM trên unix
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                warn!("Cannot listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Gửi SERVER_MESSAGE tới mọi game server đang kết nối, trả về số server đã nhận
+pub async fn notify_servers(server_registry: &ServerRegistry, text: &str) -> usize {
+    let mut notified = 0;
+    for (server_id, handle) in server_registry.all().await {
+        match Service::broadcast_message(&handle, text).await {
+            Ok(()) => notified += 1,
+            Err(e) => warn!("Cannot notify server {} of shutdown: {}", server_id, e),
+        }
+    }
+    notified
+}
+
+/// Ghi `last_time_logout` cho các user còn trong `UserManager`, trả về số user đã ghi.
+/// User vẫn được giữ lại để lưu vào danh sách online cho lần chạy sau.
+pub async fn flush_logout_times(
+    user_manager: &UserManager,
+    accounts: &dyn AccountRepository,
+) -> usize {
+    let mut flushed = 0;
+    for user in user_manager.all().await {
+        match accounts.update_logout_time(user.user_id).await {
+            Ok(()) => flushed += 1,
+            Err(e) => warn!(
+                "Cannot update logout time of {} ({}): {}",
+                user.username, user.user_id, e
+            ),
+        }
+    }
+    info!("Flushed logout time of {} online users", flushed);
+    flushed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command;
+    use crate::db::memory::{MemoryAccountRepository, account};
+    use crate::io::packet::{Packet, ServerMessage};
+    use crate::io::service::BROADCAST_CLIENT_ID;
+    use crate::testing::connect_pair;
+    use chrono::Utc;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn connected_servers_receive_broadcast() {
+        let (session, mut peer) = connect_pair(1).await;
+        let registry = ServerRegistry::new();
+        registry.register(4, session.handle().clone()).await;
+
+        assert_eq!(notify_servers(&registry, "Bảo trì").await, 1);
+        let mut msg = peer.next().await.unwrap().unwrap();
+        assert_eq!(msg.command, command::SERVER_MESSAGE);
+        let notice = ServerMessage::decode(&mut msg).unwrap();
+        assert_eq!(notice.client_id, BROADCAST_CLIENT_ID);
+        assert_eq!(notice.text, "Bảo trì");
+    }
+
+    #[tokio::test]
+    async fn online_users_get_logout_time() {
+        let accounts = MemoryAccountRepository::new();
+        for id in 1..=2 {
+            accounts.insert(account(id, &format!("user{}", id))).await;
+        }
+        let user_manager = UserManager::new();
+        user_manager.add(1, "user1".to_string(), 1, 10).await;
+        let offline_logout = accounts.get(2).await.unwrap().last_time_logout;
+        let before = Utc::now();
+
+        assert_eq!(flush_logout_times(&user_manager, &accounts).await, 1);
+        assert!(accounts.get(1).await.unwrap().last_time_logout >= before);
+        assert_eq!(
+            accounts.get(2).await.unwrap().last_time_logout,
+            offline_logout
+        );
+        assert_eq!(user_manager.all().await.len(), 1);
+    }
+}
@@ -0,0 +1,41 @@
+//! Fixture dùng chung cho test trong crate và trong `tests/`, không dùng khi chạy thật
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::codec::FramedRead;
+
+use crate::config::Config;
+use crate::io::codec::{LEGACY_KEY, MessageCodec};
+use crate::io::session::Session;
+
+/// Config tối thiểu hợp lệ, test đổi giá trị bằng `replace` hoặc ghép thêm section
+pub const CONFIG: &str = r#"
+[server]
+listen_port = 14445
+second_wait_login = 10
+testmode = 0
+
+[database]
+host = "localhost"
+port = 3306
+database_name = "nro"
+username = "root"
+password = ""
+min_connections = 1
+max_connections = 10
+"#;
+
+pub fn config() -> Config {
+    Config::parse(CONFIG, []).expect("test config is valid")
+}
+
+/// Session `id` trên một kết nối loopback (key XOR cũ) và đầu bên game server của nó
+pub async fn connect_pair(id: i32) -> (Session, FramedRead<TcpStream, MessageCodec>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let peer = TcpStream::connect(addr).await.unwrap();
+    let (stream, _) = listener.accept().await.unwrap();
+    (
+        Session::new(stream, addr.to_string(), id, LEGACY_KEY.to_vec()),
+        FramedRead::new(peer, MessageCodec::new(LEGACY_KEY.to_vec())),
+    )
+}
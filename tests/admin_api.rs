@@ -0,0 +1,302 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+use futures::StreamExt;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::codec::FramedRead;
+use tokio_util::sync::CancellationToken;
+
+use login_server_rust::admin;
+use login_server_rust::command;
+use login_server_rust::config::SharedConfig;
+use login_server_rust::context::AppContext;
+use login_server_rust::db::memory::{MemoryAccountRepository, account};
+use login_server_rust::io::codec::MessageCodec;
+use login_server_rust::io::link_cleanup::LinkCleanup;
+use login_server_rust::io::packet::{Disconnect, Packet, ServerMessage};
+use login_server_rust::io::server_registry::ServerRegistry;
+use login_server_rust::io::session::Session;
+use login_server_rust::metrics::Metrics;
+use login_server_rust::model::login_history::LoginHistory;
+use login_server_rust::model::login_limiter::LoginLimiter;
+use login_server_rust::model::maintenance::Maintenance;
+use login_server_rust::model::password::PasswordHasher;
+use login_server_rust::model::user_manager::UserManager;
+use login_server_rust::testing::{self, connect_pair};
+
+const TOKEN: &str = "test-token";
+
+struct Api {
+    base: String,
+    client: reqwest::Client,
+    accounts: MemoryAccountRepository,
+    user_manager: UserManager,
+    server_registry: ServerRegistry,
+    maintenance: Maintenance,
+    _shutdown: tokio_util::sync::DropGuard,
+}
+
+impl Api {
+    async fn start() -> Self {
+        let config = testing::config();
+        let accounts = MemoryAccountRepository::new();
+        let user_manager = UserManager::new();
+        let server_registry = ServerRegistry::new();
+        let maintenance = Maintenance::new();
+        let app = AppContext {
+            accounts: Arc::new(accounts.clone()),
+            user_manager: user_manager.clone(),
+            server_registry: server_registry.clone(),
+            link_cleanup: LinkCleanup::new(
+                user_manager.clone(),
+                Arc::new(accounts.clone()),
+                Duration::from_secs(60),
+            ),
+            password_hasher: PasswordHasher::new(&config.password).unwrap(),
+            login_limiter: LoginLimiter::new(config.login_limit.clone()),
+            maintenance: maintenance.clone(),
+            login_history: LoginHistory::disabled(),
+            metrics: Metrics::new(),
+            config: SharedConfig::new(config),
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let shutdown = CancellationToken::new();
+        tokio::spawn(admin::serve(listener, app, TOKEN, shutdown.clone()));
+        Self {
+            base,
+            client: reqwest::Client::new(),
+            accounts,
+            user_manager,
+            server_registry,
+            maintenance,
+            _shutdown: shutdown.drop_guard(),
+        }
+    }
+
+    async fn get(&self, path: &str) -> (StatusCode, Value) {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base, path))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        (response.status(), response.json().await.unwrap())
+    }
+
+    async fn post(&self, path: &str, body: Value) -> (StatusCode, Value) {
+        let response = self
+            .client
+            .post(format!("{}{}", self.base, path))
+            .bearer_auth(TOKEN)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        (response.status(), response.json().await.unwrap())
+    }
+
+    /// Giả lập game server `server_id` đã gửi SET_SERVER, trả về đầu bên game server
+    async fn connect_server(
+        &self,
+        server_id: i32,
+    ) -> (Session, FramedRead<TcpStream, MessageCodec>) {
+        let (session, peer) = connect_pair(server_id).await;
+        self.server_registry
+            .register(server_id, session.handle().clone())
+            .await;
+        (session, peer)
+    }
+}
+
+#[tokio::test]
+async fn requests_without_token_are_rejected() {
+    let api = Api::start().await;
+    let response = api
+        .client
+        .get(format!("{}/status", api.base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = api
+        .client
+        .get(format!("{}/status", api.base))
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn online_users_can_be_listed_and_searched() {
+    let api = Api::start().await;
+    api.user_manager
+        .add(2, "NguyenVan".to_string(), 1, 10)
+        .await;
+    api.user_manager.add(1, "tranthi".to_string(), 1, 11).await;
+    api.user_manager
+        .add(3, "nguyenthi".to_string(), 2, 12)
+        .await;
+
+    let (status, users) = api.get("/users").await;
+    assert_eq!(status, StatusCode::OK);
+    let ids: Vec<_> = users
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|u| u["user_id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    let (_, users) = api.get("/users?server_id=1&q=nguyen").await;
+    assert_eq!(
+        users,
+        json!([{ "user_id": 2, "username": "NguyenVan", "server_id": 1, "client_id": 10 }])
+    );
+}
+
+#[tokio::test]
+async fn kick_sends_disconnect_to_owning_server() {
+    let api = Api::start().await;
+    let (_session, mut game_server) = api.connect_server(2).await;
+    api.accounts.insert(account(5, "player")).await;
+    api.user_manager.add(5, "player".to_string(), 2, 10).await;
+
+    let (status, body) = api.post("/users/5/kick", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["server_id"], 2);
+    assert_eq!(body["delivered"], true);
+    let mut msg = game_server.next().await.unwrap().unwrap();
+    assert_eq!(msg.command, command::DISCONNECT);
+    assert_eq!(Disconnect::decode(&mut msg).unwrap().user_id, 5);
+    assert!(api.user_manager.find(5).await.is_none());
+
+    let (status, _) = api.post("/users/5/kick", json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn ban_marks_account_and_kicks_online_user() {
+    let api = Api::start().await;
+    let (_session, mut game_server) = api.connect_server(1).await;
+    api.accounts.insert(account(7, "cheater")).await;
+    api.user_manager.add(7, "cheater".to_string(), 1, 10).await;
+
+    let (status, body) = api.post("/accounts/7/ban", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["kicked"]["delivered"], true);
+    assert!(api.accounts.get(7).await.unwrap().ban);
+    assert_eq!(
+        game_server.next().await.unwrap().unwrap().command,
+        command::DISCONNECT
+    );
+
+    let (status, body) = api.post("/accounts/7/unban", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["kicked"], Value::Null);
+    assert!(!api.accounts.get(7).await.unwrap().ban);
+
+    let (status, _) = api.post("/accounts/99/ban", json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn broadcast_and_status_cover_connected_servers() {
+    let api = Api::start().await;
+    let (_first, mut first) = api.connect_server(1).await;
+    let (_second, _) = api.connect_server(2).await;
+    api.user_manager.add(1, "a".to_string(), 1, 10).await;
+    api.user_manager.add(2, "b".to_string(), 3, 11).await;
+
+    let (status, body) = api
+        .post(
+            "/broadcast",
+            json!({ "text": "Bảo trì lúc 22h", "server_id": 1 }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["notified"], 1);
+    let mut msg = first.next().await.unwrap().unwrap();
+    assert_eq!(msg.command, command::SERVER_MESSAGE);
+    assert_eq!(
+        ServerMessage::decode(&mut msg).unwrap().text,
+        "Bảo trì lúc 22h"
+    );
+
+    let (status, _) = api.post("/broadcast", json!({ "text": " " })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = api.get("/status").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["online_users"], 2);
+    let servers: Vec<_> = body["servers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| {
+            (
+                s["server_id"].clone(),
+                s["connected"].clone(),
+                s["online_users"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        servers,
+        vec![
+            (json!(1), json!(true), json!(1)),
+            (json!(2), json!(true), json!(0)),
+            (json!(3), json!(false), json!(1)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn maintenance_can_be_switched_at_runtime() {
+    let api = Api::start().await;
+    let until = Utc::now() + TimeDelta::hours(1);
+    let response = api
+        .client
+        .put(format!("{}/maintenance/2", api.base))
+        .bearer_auth(TOKEN)
+        .json(&json!({ "message": "Cập nhật", "until": until, "whitelist": ["tester"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["server_id"], 2);
+    assert_eq!(api.maintenance.all().await.len(), 1);
+
+    let (_, list) = api.get("/maintenance").await;
+    assert_eq!(list[0]["whitelist"], json!(["tester"]));
+
+    let past = Utc::now() - TimeDelta::hours(1);
+    let response = api
+        .client
+        .put(format!("{}/maintenance/3", api.base))
+        .bearer_auth(TOKEN)
+        .json(&json!({ "until": past }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let delete = |server_id: i32| {
+        api.client
+            .delete(format!("{}/maintenance/{}", api.base, server_id))
+            .bearer_auth(TOKEN)
+            .send()
+    };
+    assert_eq!(delete(2).await.unwrap().status(), StatusCode::OK);
+    assert_eq!(delete(2).await.unwrap().status(), StatusCode::NOT_FOUND);
+    assert!(api.maintenance.all().await.is_empty());
+}